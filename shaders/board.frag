@@ -0,0 +1,131 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec2 TexCoord;
+
+// Value of each cell, depending on the colouring:
+// - trail: 0 for live cells, then 1 to 254 along the trail, 255 past it
+// - age: 0 for dead cells, then 1 to 255 from newborn cells to the end of the age range
+// - heat: 0 to 255 from no change to a change in every generation of the heat window
+uniform usampler2D state;
+// Highlight flags of each cell, as in src/render/mod.rs
+uniform usampler2D highlights;
+
+uniform vec3 dead_color;
+uniform vec3 alive_color;
+// Live cells of still lifes, oscillators, spaceships and active objects
+uniform vec3 object_colors[4];
+uniform vec3 selection_color;
+uniform vec3 paste_area_color;
+uniform vec3 paste_color;
+uniform vec3 gradient_low;
+uniform vec3 gradient_middle;
+uniform vec3 gradient_high;
+
+// 0 for trail, 1 for age, 2 for heat
+uniform uint coloring;
+
+uniform vec3 trail_color;
+// 0 for linear, 1 for exponential, 2 for smooth, 3 for constant
+uniform uint trail_decay;
+
+uniform vec3 grid_color;
+// Cells between major grid lines, 0 when the grid is hidden
+uniform uint grid_major;
+
+// Spacing of grid lines in pixels over which they fade in when zooming in
+const float GRID_FADE_START = 4.0f;
+const float GRID_FADE_END = 8.0f;
+
+// min_x, min_y, max_x, max_y of the selected cells, empty when nothing is selected
+uniform vec4 selection;
+
+const uint PASTE_ALIVE = 64u;
+const uint PASTE_DEAD = 128u;
+
+// Strength of the trail colour at `t`, from 0 at the start of the trail to 1 at its end
+float decay(float t) {
+    if (trail_decay == 1u) {
+        return (exp(-5.0f * t) - exp(-5.0f)) / (1.0f - exp(-5.0f));
+    } else if (trail_decay == 2u) {
+        return 1.0f - smoothstep(0.0f, 1.0f, t);
+    } else if (trail_decay == 3u) {
+        return 1.0f;
+    }
+
+    return 1.0f - t;
+}
+
+// Strength of grid lines every `spacing` cells at `position`, in cells, with `cell_pixels` the
+// size of a cell in pixels
+float grid_line(vec2 position, vec2 cell_pixels, float spacing) {
+    // Distance in pixels to the closest line on each axis, from the left or top edge of the
+    // pixel so that each line is one pixel wide
+    vec2 edge = position - 0.5f / cell_pixels;
+    vec2 distance = abs(edge / spacing - round(edge / spacing)) * spacing * cell_pixels;
+
+    float line_pixels = min(cell_pixels.x, cell_pixels.y) * spacing;
+    float visible = smoothstep(GRID_FADE_START, GRID_FADE_END, line_pixels);
+
+    return any(lessThan(distance, vec2(0.5f))) ? visible : 0.0f;
+}
+
+vec3 gradient(float t) {
+    return t < 0.5f
+        ? mix(gradient_low, gradient_middle, t * 2.0f)
+        : mix(gradient_middle, gradient_high, t * 2.0f - 1.0f);
+}
+
+void main() {
+    ivec2 size = textureSize(state, 0);
+    vec2 position = TexCoord * vec2(size);
+    ivec2 cell = min(ivec2(position), size - 1);
+
+    // Outside of the branches, as derivatives need all the neighbouring fragments
+    vec2 cell_pixels = 1.0f / fwidth(position);
+
+    uint value = texelFetch(state, cell, 0).r;
+    uint flags = texelFetch(highlights, cell, 0).r;
+    // Classes of the object the cell is part of, and of the box over it, 0 for none
+    uint object_class = flags & 7u;
+    uint box_class = (flags >> 3) & 7u;
+
+    vec3 color = dead_color;
+
+    if (coloring == 1u) {
+        if (value != 0u) {
+            color = gradient(float(value - 1u) / 254.0f);
+        }
+    } else if (coloring == 2u) {
+        if (value != 0u) {
+            color = gradient(float(value) / 255.0f);
+        }
+    } else if (value == 0u) {
+        color = object_class != 0u ? object_colors[object_class - 1u] : alive_color;
+    } else if (value != 255u) {
+        color = mix(dead_color, trail_color, decay(float(value - 1u) / 254.0f));
+    }
+
+    if (box_class != 0u) {
+        color = mix(color, object_colors[box_class - 1u], 0.35f);
+    }
+
+    if (grid_major != 0u) {
+        float minor = 0.5f * grid_line(position, cell_pixels, 1.0f);
+        float major = grid_line(position, cell_pixels, float(grid_major));
+
+        color = mix(color, grid_color, max(minor, major));
+    }
+
+    if (all(greaterThanEqual(vec2(cell), selection.xy)) && all(lessThanEqual(vec2(cell), selection.zw))) {
+        color = mix(color, selection_color, 0.5f);
+    }
+
+    if ((flags & PASTE_ALIVE) != 0u) {
+        color = paste_color;
+    } else if ((flags & PASTE_DEAD) != 0u) {
+        color = mix(color, paste_area_color, 0.5f);
+    }
+
+    FragColor = vec4(color, 1.0f);
+}
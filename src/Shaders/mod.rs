@@ -1,59 +1,147 @@
-struct VertexShader {
-    id: u32,
+use nalgebra_glm as glm;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+
+mod reload;
+
+pub use reload::WatchedProgram;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ShaderStage {
+    Vertex,
+    Fragment,
+    Link,
 }
 
-impl Drop for VertexShader {
-    fn drop(&mut self) {
-        unsafe {
-            gl::DeleteShader(self.id);
+impl fmt::Display for ShaderStage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ShaderStage::Vertex => write!(f, "Vertex shader compilation"),
+            ShaderStage::Fragment => write!(f, "Fragment shader compilation"),
+            ShaderStage::Link => write!(f, "Shader program linking"),
         }
     }
 }
 
-impl VertexShader {
-    pub fn new(vertex_shader_source: &str) -> Self {
-        let vertex_shader_source = std::ffi::CString::new(vertex_shader_source).unwrap();
+#[derive(Debug)]
+pub struct ShaderError {
+    pub stage: ShaderStage,
+    // Info log of the driver
+    pub log: String,
+    // Source lines the log refers to, with their line number
+    pub context: Vec<(usize, String)>,
+}
 
-        let mut vertex_shader_sources = Vec::new();
-        vertex_shader_sources.push(vertex_shader_source.as_ptr());
+impl ShaderError {
+    fn new(stage: ShaderStage, log: String, source: &str) -> Self {
+        let lines: Vec<&str> = source.lines().collect();
 
-        let vertex_shader = unsafe { gl::CreateShader(gl::VERTEX_SHADER) };
+        let mut numbers: Vec<usize> = log.lines().filter_map(log_line_number).collect();
+        numbers.sort_unstable();
+        numbers.dedup();
 
-        unsafe {
-            gl::ShaderSource(
-                vertex_shader,
-                1,
-                vertex_shader_sources.as_ptr(),
-                std::ptr::null(),
-            );
+        let context = numbers
+            .into_iter()
+            .filter(|&number| number >= 1 && number <= lines.len())
+            .map(|number| (number, lines[number - 1].trim().to_string()))
+            .collect();
+
+        ShaderError {
+            stage,
+            log,
+            context,
+        }
+    }
+}
 
-            gl::CompileShader(vertex_shader);
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} failed", self.stage)?;
+        write!(f, "{}", self.log.trim_end())?;
 
-            let mut success = 0;
+        for (number, line) in &self.context {
+            write!(f, "\n{:>4} | {}", number, line)?;
+        }
 
-            gl::GetShaderiv(vertex_shader, gl::COMPILE_STATUS, &mut success);
-            if success <= 0 {
-                let mut len = 0;
-                gl::GetShaderiv(vertex_shader, gl::INFO_LOG_LENGTH, &mut len);
+        Ok(())
+    }
+}
 
-                let mut buffer = Vec::with_capacity(len as usize);
+impl std::error::Error for ShaderError {}
 
-                gl::GetShaderInfoLog(
-                    vertex_shader,
-                    len,
-                    std::ptr::null_mut(),
-                    buffer.as_mut_ptr(),
-                );
+// Source line a driver log line refers to. Mesa writes "0:12(5): error: ..." and NVIDIA
+// "0(12) : error C0000: ...", with 0 being the source string and 12 the line
+fn log_line_number(line: &str) -> Option<usize> {
+    let rest = line.trim_start().strip_prefix('0')?;
 
-                let log_info = buffer.iter().map(|&x| x as u8).collect();
-                println!(
-                    "Vertex shader compilation failed\n{}\n",
-                    String::from_utf8_unchecked(log_info)
-                );
-            }
+    let digits = rest.strip_prefix(':').or_else(|| rest.strip_prefix('('))?;
+
+    let end = digits
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(digits.len());
+
+    digits[..end].parse().ok()
+}
+
+fn compile(kind: u32, stage: ShaderStage, source: &str) -> Result<u32, ShaderError> {
+    let source_c = std::ffi::CString::new(source).map_err(|_| {
+        ShaderError::new(stage, "The source contains a NUL byte".to_string(), source)
+    })?;
+
+    unsafe {
+        let shader = gl::CreateShader(kind);
+
+        gl::ShaderSource(shader, 1, &source_c.as_ptr(), std::ptr::null());
+        gl::CompileShader(shader);
+
+        let mut success = 0;
+        gl::GetShaderiv(shader, gl::COMPILE_STATUS, &mut success);
+
+        if success <= 0 {
+            let mut len = 0;
+            gl::GetShaderiv(shader, gl::INFO_LOG_LENGTH, &mut len);
+
+            let mut buffer = vec![0u8; len.max(1) as usize];
+            let mut written = 0;
+            gl::GetShaderInfoLog(
+                shader,
+                buffer.len() as i32,
+                &mut written,
+                buffer.as_mut_ptr() as *mut gl::types::GLchar,
+            );
+            buffer.truncate(written as usize);
+
+            gl::DeleteShader(shader);
+
+            return Err(ShaderError::new(
+                stage,
+                String::from_utf8_lossy(&buffer).into_owned(),
+                source,
+            ));
         }
 
-        VertexShader { id: vertex_shader }
+        Ok(shader)
+    }
+}
+
+struct VertexShader {
+    id: u32,
+}
+
+impl Drop for VertexShader {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteShader(self.id);
+        }
+    }
+}
+
+impl VertexShader {
+    pub fn new(vertex_shader_source: &str) -> Result<Self, ShaderError> {
+        let id = compile(gl::VERTEX_SHADER, ShaderStage::Vertex, vertex_shader_source)?;
+
+        Ok(VertexShader { id })
     }
 }
 
@@ -70,94 +158,170 @@ impl Drop for FragmentShader {
 }
 
 impl FragmentShader {
-    pub fn new(fragment_shader_source: &str) -> Self {
-        let fragment_shader_source = std::ffi::CString::new(fragment_shader_source).unwrap();
-
-        let mut fragment_shader_sources = Vec::new();
-        fragment_shader_sources.push(fragment_shader_source.as_ptr());
+    pub fn new(fragment_shader_source: &str) -> Result<Self, ShaderError> {
+        let id = compile(
+            gl::FRAGMENT_SHADER,
+            ShaderStage::Fragment,
+            fragment_shader_source,
+        )?;
+
+        Ok(FragmentShader { id })
+    }
+}
 
-        let fragment_shader = unsafe { gl::CreateShader(gl::FRAGMENT_SHADER) };
+// Value that can be given to a uniform of the matching GLSL type
+pub trait Uniform {
+    // Sets the uniform at `location` of the program in use
+    fn set(&self, location: i32);
+}
 
-        unsafe {
-            gl::ShaderSource(
-                fragment_shader,
-                1,
-                fragment_shader_sources.as_ptr(),
-                std::ptr::null(),
-            );
+impl Uniform for i32 {
+    fn set(&self, location: i32) {
+        unsafe { gl::Uniform1i(location, *self) }
+    }
+}
 
-            gl::CompileShader(fragment_shader);
+impl Uniform for u32 {
+    fn set(&self, location: i32) {
+        unsafe { gl::Uniform1ui(location, *self) }
+    }
+}
 
-            let mut success = 0;
+impl Uniform for f32 {
+    fn set(&self, location: i32) {
+        unsafe { gl::Uniform1f(location, *self) }
+    }
+}
 
-            gl::GetShaderiv(fragment_shader, gl::COMPILE_STATUS, &mut success);
-            if success <= 0 {
-                let mut len = 0;
-                gl::GetShaderiv(fragment_shader, gl::INFO_LOG_LENGTH, &mut len);
+impl Uniform for glm::Vec2 {
+    fn set(&self, location: i32) {
+        unsafe { gl::Uniform2fv(location, 1, self.as_ptr()) }
+    }
+}
 
-                let mut buffer = Vec::with_capacity(len as usize);
+impl Uniform for glm::Vec3 {
+    fn set(&self, location: i32) {
+        unsafe { gl::Uniform3fv(location, 1, self.as_ptr()) }
+    }
+}
 
-                gl::GetShaderInfoLog(
-                    fragment_shader,
-                    len,
-                    std::ptr::null_mut(),
-                    buffer.as_mut_ptr(),
-                );
+impl Uniform for glm::Vec4 {
+    fn set(&self, location: i32) {
+        unsafe { gl::Uniform4fv(location, 1, self.as_ptr()) }
+    }
+}
 
-                let log_info = buffer.iter().map(|&x| x as u8).collect();
-                println!(
-                    "Fragment shader compilation failed\n{}\n",
-                    String::from_utf8_unchecked(log_info)
-                );
-            }
+impl Uniform for glm::Mat3 {
+    fn set(&self, location: i32) {
+        unsafe { gl::UniformMatrix3fv(location, 1, gl::FALSE, self.as_ptr()) }
+    }
+}
 
-            FragmentShader {
-                id: fragment_shader,
-            }
-        }
+impl Uniform for glm::Mat4 {
+    fn set(&self, location: i32) {
+        unsafe { gl::UniformMatrix4fv(location, 1, gl::FALSE, self.as_ptr()) }
     }
 }
 
 pub struct ShaderProgram {
     pub id: u32,
+    // Uniform locations already looked up, -1 for unknown names
+    locations: RefCell<HashMap<String, i32>>,
 }
 
-impl ShaderProgram {
-    pub fn new(vertex_shader_source: &str, fragment_shader_source: &str) -> Self {
-        let vs = VertexShader::new(vertex_shader_source);
-        let fs = FragmentShader::new(fragment_shader_source);
+impl Drop for ShaderProgram {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteProgram(self.id);
+        }
+    }
+}
 
-        let shader_program = unsafe { gl::CreateProgram() };
+impl ShaderProgram {
+    pub fn new(
+        vertex_shader_source: &str,
+        fragment_shader_source: &str,
+    ) -> Result<Self, ShaderError> {
+        let vs = VertexShader::new(vertex_shader_source)?;
+        let fs = FragmentShader::new(fragment_shader_source)?;
+
+        // Owned from here, so the program is deleted if linking fails
+        let shader_program = ShaderProgram {
+            id: unsafe { gl::CreateProgram() },
+            locations: RefCell::new(HashMap::new()),
+        };
 
         unsafe {
-            gl::AttachShader(shader_program, vs.id);
-            gl::AttachShader(shader_program, fs.id);
-            gl::LinkProgram(shader_program);
+            gl::AttachShader(shader_program.id, vs.id);
+            gl::AttachShader(shader_program.id, fs.id);
+            gl::LinkProgram(shader_program.id);
 
             let mut success = 0;
+            gl::GetProgramiv(shader_program.id, gl::LINK_STATUS, &mut success);
 
-            gl::GetProgramiv(shader_program, gl::LINK_STATUS, &mut success);
             if success <= 0 {
                 let mut len = 0;
-                gl::GetProgramiv(shader_program, gl::INFO_LOG_LENGTH, &mut len);
-
-                let mut buffer = Vec::with_capacity(len as usize);
+                gl::GetProgramiv(shader_program.id, gl::INFO_LOG_LENGTH, &mut len);
 
+                let mut buffer = vec![0u8; len.max(1) as usize];
+                let mut written = 0;
                 gl::GetProgramInfoLog(
-                    shader_program,
-                    len,
-                    std::ptr::null_mut(),
-                    buffer.as_mut_ptr(),
-                );
-
-                let log_info = buffer.iter().map(|&x| x as u8).collect();
-                println!(
-                    "Shader program shader compilation failed\n{}\n",
-                    String::from_utf8_unchecked(log_info)
+                    shader_program.id,
+                    buffer.len() as i32,
+                    &mut written,
+                    buffer.as_mut_ptr() as *mut gl::types::GLchar,
                 );
+                buffer.truncate(written as usize);
+
+                // Link errors don't point at a single source, so there is no line context
+                return Err(ShaderError::new(
+                    ShaderStage::Link,
+                    String::from_utf8_lossy(&buffer).into_owned(),
+                    "",
+                ));
             }
 
-            ShaderProgram { id: shader_program }
+            gl::DetachShader(shader_program.id, vs.id);
+            gl::DetachShader(shader_program.id, fs.id);
         }
+
+        Ok(shader_program)
+    }
+
+    fn uniform_location(&self, name: &str) -> i32 {
+        if let Some(&location) = self.locations.borrow().get(name) {
+            return location;
+        }
+
+        let location = match std::ffi::CString::new(name) {
+            Ok(name_c) => unsafe { gl::GetUniformLocation(self.id, name_c.as_ptr()) },
+            Err(_) => -1,
+        };
+
+        // Also returned for uniforms the compiler optimised away, which is worth knowing about
+        if location < 0 {
+            eprintln!(
+                "Warning: shader program {} has no uniform \"{}\"",
+                self.id, name
+            );
+        }
+
+        self.locations
+            .borrow_mut()
+            .insert(name.to_string(), location);
+
+        location
+    }
+
+    // Sets a uniform of the program, which becomes the program in use
+    pub fn set_uniform<T: Uniform>(&self, name: &str, value: T) {
+        let location = self.uniform_location(name);
+
+        unsafe {
+            gl::UseProgram(self.id);
+        }
+
+        // Setting location -1 is silently ignored by OpenGL
+        value.set(location);
     }
 }
@@ -0,0 +1,119 @@
+use super::{ShaderError, ShaderProgram};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+// How often the source files are checked for changes
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+// A shader source file, with the copy built into the executable used when it can't be read
+struct SourceFile {
+    path: PathBuf,
+    fallback: &'static str,
+    modified: Option<SystemTime>,
+}
+
+impl SourceFile {
+    fn new(path: PathBuf, fallback: &'static str) -> Self {
+        let modified = modified_time(&path);
+
+        SourceFile {
+            path,
+            fallback,
+            modified,
+        }
+    }
+
+    fn read(&self) -> String {
+        fs::read_to_string(&self.path).unwrap_or_else(|_| self.fallback.to_string())
+    }
+
+    // Whether the file was modified, created or removed since the last check
+    fn changed(&mut self) -> bool {
+        let modified = modified_time(&self.path);
+        let changed = modified != self.modified;
+        self.modified = modified;
+
+        changed
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+// Shader program built from a vertex and a fragment shader file, and rebuilt when either of
+// them changes on disk. A rebuild that fails keeps the previous program running
+pub struct WatchedProgram {
+    pub program: ShaderProgram,
+    // Error of the last build, cleared once the files build again
+    pub error: Option<ShaderError>,
+    vertex: SourceFile,
+    fragment: SourceFile,
+    last_poll: Instant,
+}
+
+impl WatchedProgram {
+    // The fallbacks are the sources used for files that can't be read
+    pub fn new(
+        vertex_path: impl Into<PathBuf>,
+        fragment_path: impl Into<PathBuf>,
+        vertex_fallback: &'static str,
+        fragment_fallback: &'static str,
+    ) -> Result<Self, ShaderError> {
+        let vertex = SourceFile::new(vertex_path.into(), vertex_fallback);
+        let fragment = SourceFile::new(fragment_path.into(), fragment_fallback);
+
+        // Broken files on disk shouldn't stop the program from starting
+        let (program, error) = match ShaderProgram::new(&vertex.read(), &fragment.read()) {
+            Ok(program) => (program, None),
+            Err(error) => {
+                eprintln!("{}", error);
+                (
+                    ShaderProgram::new(vertex_fallback, fragment_fallback)?,
+                    Some(error),
+                )
+            }
+        };
+
+        Ok(WatchedProgram {
+            program,
+            error,
+            vertex,
+            fragment,
+            last_poll: Instant::now(),
+        })
+    }
+
+    // Rebuilds the program if a source file changed. Returns true when the program was
+    // replaced, as the uniforms of the new program have to be set again
+    pub fn reload_if_changed(&mut self) -> bool {
+        if self.last_poll.elapsed() < POLL_INTERVAL {
+            return false;
+        }
+        self.last_poll = Instant::now();
+
+        // Both are checked so that each change is only seen once
+        let vertex_changed = self.vertex.changed();
+        let fragment_changed = self.fragment.changed();
+
+        if !vertex_changed && !fragment_changed {
+            return false;
+        }
+
+        match ShaderProgram::new(&self.vertex.read(), &self.fragment.read()) {
+            Ok(program) => {
+                self.program = program;
+                self.error = None;
+                true
+            }
+            Err(error) => {
+                eprintln!("{}", error);
+                self.error = Some(error);
+                false
+            }
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use nalgebra_glm as glm;
+
+const MIN_ZOOM: f32 = 0.1;
+const MAX_ZOOM: f32 = 200.0;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ResizeMode {
+    // Scales the view so what was visible stays visible, leaving bars on the longer side
+    Letterbox,
+    // Keeps the size of the cells, showing more or less of the board
+    Expand,
+}
+
+// Orthographic camera over the board. World coordinates are board coordinates in cells (y down,
+// see `GameOfLife`), with the board spanning (0, 0) to (width, height)
+pub struct Camera {
+    // World position shown at the centre of the viewport
+    pub center: glm::Vec2,
+    // Size of a cell on screen, in pixels
+    pub zoom: f32,
+}
+
+impl Camera {
+    pub fn new(center: glm::Vec2, zoom: f32) -> Self {
+        Camera {
+            center,
+            zoom: zoom.clamp(MIN_ZOOM, MAX_ZOOM),
+        }
+    }
+
+    pub fn view_projection(&self, (viewport_width, viewport_height): (u32, u32)) -> glm::Mat4 {
+        let half_width = viewport_width as f32 / 2.0 / self.zoom;
+        let half_height = viewport_height as f32 / 2.0 / self.zoom;
+
+        // Bottom and top are swapped, as world y grows downwards but OpenGL's grows upwards
+        glm::ortho(
+            self.center.x - half_width,
+            self.center.x + half_width,
+            self.center.y + half_height,
+            self.center.y - half_height,
+            -1.0,
+            1.0,
+        )
+    }
+
+    // World position under a point of the viewport, given in pixels from its top left corner
+    pub fn window_to_world(
+        &self,
+        x: f32,
+        y: f32,
+        (viewport_width, viewport_height): (u32, u32),
+    ) -> glm::Vec2 {
+        let offset = glm::vec2(
+            x - viewport_width as f32 / 2.0,
+            y - viewport_height as f32 / 2.0,
+        );
+
+        self.center + offset / self.zoom
+    }
+
+    // Point of the viewport, in pixels from its top left corner, showing a world position
+    pub fn world_to_window(
+        &self,
+        position: glm::Vec2,
+        (viewport_width, viewport_height): (u32, u32),
+    ) -> glm::Vec2 {
+        let center = glm::vec2(viewport_width as f32 / 2.0, viewport_height as f32 / 2.0);
+
+        center + (position - self.center) * self.zoom
+    }
+
+    // Moves the camera by an offset in pixels, so dragging follows the mouse
+    pub fn pan(&mut self, dx: f32, dy: f32) {
+        self.center -= glm::vec2(dx, dy) / self.zoom;
+    }
+
+    // Zooms by a factor while keeping the world position under the window point in place
+    pub fn zoom_at(&mut self, factor: f32, x: f32, y: f32, viewport: (u32, u32)) {
+        let anchor = self.window_to_world(x, y, viewport);
+
+        self.zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+
+        let moved_anchor = self.window_to_world(x, y, viewport);
+        self.center += anchor - moved_anchor;
+    }
+
+    // Keeps the view centred when the viewport changes size
+    pub fn resize(
+        &mut self,
+        (old_width, old_height): (u32, u32),
+        (new_width, new_height): (u32, u32),
+        mode: ResizeMode,
+    ) {
+        if mode == ResizeMode::Letterbox && old_width > 0 && old_height > 0 {
+            let factor =
+                (new_width as f32 / old_width as f32).min(new_height as f32 / old_height as f32);
+            self.zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        }
+    }
+
+    // Centres and zooms the camera so the world rectangle fills the viewport, with a small margin
+    pub fn fit(
+        &mut self,
+        min: glm::Vec2,
+        max: glm::Vec2,
+        (viewport_width, viewport_height): (u32, u32),
+    ) {
+        let size = (max - min).add_scalar(2.0);
+
+        self.center = (min + max) / 2.0;
+        self.zoom = (viewport_width as f32 / size.x)
+            .min(viewport_height as f32 / size.y)
+            .clamp(MIN_ZOOM, MAX_ZOOM);
+    }
+}
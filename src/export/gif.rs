@@ -0,0 +1,40 @@
+use std::fs::File;
+use std::path::Path;
+
+use image::codecs::gif::{GifEncoder, Repeat};
+
+// Speed of the colour quantization, from 1 (best) to 30 (fastest). The boards have few colours
+const QUANTIZATION_SPEED: i32 = 10;
+
+// Animated GIF written a frame at a time, looping forever
+pub struct GifRecorder {
+    encoder: GifEncoder<File>,
+    // Time each frame is shown, in milliseconds
+    delay: u32,
+}
+
+impl GifRecorder {
+    pub fn create(path: &Path, delay: u32) -> Result<Self, String> {
+        let file =
+            File::create(path).map_err(|e| format!("Can't create {}: {}", path.display(), e))?;
+
+        let mut encoder = GifEncoder::new_with_speed(file, QUANTIZATION_SPEED);
+        encoder
+            .set_repeat(Repeat::Infinite)
+            .map_err(|e| e.to_string())?;
+
+        Ok(GifRecorder { encoder, delay })
+    }
+
+    // Adds a frame, which must have the size of the first one
+    pub fn add_frame(&mut self, image: image::RgbaImage) -> Result<(), String> {
+        self.encoder
+            .encode_frame(image::Frame::from_parts(
+                image,
+                0,
+                0,
+                image::Delay::from_numer_denom_ms(self.delay, 1),
+            ))
+            .map_err(|e| e.to_string())
+    }
+}
@@ -0,0 +1,134 @@
+use crate::GameOfLife;
+
+pub mod gif;
+pub mod svg;
+pub mod video;
+
+// Part of the board exported
+#[derive(Clone, Copy, PartialEq)]
+pub enum Crop {
+    Board,
+    // Cells from (min_x, min_y) to (max_x, max_y), clipped to the board
+    Region(u32, u32, u32, u32),
+    // Bounding box of the live cells over the exported generations
+    Pattern,
+}
+
+impl Crop {
+    // Parses "pattern", "board" or "X,Y,WIDTH,HEIGHT"
+    pub fn parse(text: &str) -> Result<Self, String> {
+        match text {
+            "board" => return Ok(Crop::Board),
+            "pattern" => return Ok(Crop::Pattern),
+            _ => {}
+        }
+
+        let numbers: Vec<u32> = text
+            .split(',')
+            .map(|number| number.trim().parse())
+            .collect::<Result<_, _>>()
+            .map_err(|_| format!("Invalid crop \"{}\"", text))?;
+
+        let invalid = || {
+            format!(
+                "Invalid crop \"{}\", expected pattern, board or X,Y,WIDTH,HEIGHT",
+                text
+            )
+        };
+
+        match numbers[..] {
+            [x, y, width, height] if width > 0 && height > 0 => {
+                // The last cell, which must be a u32 like the board coordinates
+                let last =
+                    |start: u32, length: u32| start.checked_add(length - 1).ok_or_else(invalid);
+                Ok(Crop::Region(x, y, last(x, width)?, last(y, height)?))
+            }
+            _ => Err(invalid()),
+        }
+    }
+
+    // Checks that a region starts on `board`, as it would be clipped to nothing
+    pub fn validate(self, board: &GameOfLife) -> Result<(), String> {
+        match self {
+            Crop::Region(min_x, min_y, _, _) if min_x >= board.width || min_y >= board.height => {
+                Err(format!(
+                    "The crop region starts at {},{}, outside of the {}x{} board",
+                    min_x, min_y, board.width, board.height
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // Cells exported from `board` and the next `generations` generations, as
+    // (min_x, min_y, max_x, max_y). Regions must have been validated on a board of this size
+    pub fn region(self, board: &GameOfLife, generations: u64) -> (u32, u32, u32, u32) {
+        let whole = (0, 0, board.width - 1, board.height - 1);
+
+        match self {
+            Crop::Board => whole,
+            // The whole board when it stays empty
+            Crop::Pattern => run_bounds(board, generations).unwrap_or(whole),
+            Crop::Region(min_x, min_y, max_x, max_y) => (
+                min_x.min(whole.2),
+                min_y.min(whole.3),
+                max_x.min(whole.2),
+                max_y.min(whole.3),
+            ),
+        }
+    }
+}
+
+// Union of the bounding boxes of the live cells of `board` and of the next `generations`
+// generations
+pub fn run_bounds(board: &GameOfLife, generations: u64) -> Option<(u32, u32, u32, u32)> {
+    let mut bounds = board.population_bounds();
+    let mut later: Option<GameOfLife> = None;
+
+    for _ in 0..generations {
+        let next = later.as_ref().unwrap_or(board).simulate();
+
+        bounds = match (bounds, next.population_bounds()) {
+            (Some(a), Some(b)) => Some((a.0.min(b.0), a.1.min(b.1), a.2.max(b.2), a.3.max(b.3))),
+            (a, b) => a.or(b),
+        };
+        later = Some(next);
+    }
+
+    bounds
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_regions() {
+        assert!(Crop::parse("2,3,4,5") == Ok(Crop::Region(2, 3, 5, 7)));
+        assert!(Crop::parse(" 0, 0, 1, 1") == Ok(Crop::Region(0, 0, 0, 0)));
+        assert!(Crop::parse("board") == Ok(Crop::Board));
+        assert!(Crop::parse("pattern") == Ok(Crop::Pattern));
+        assert!(Crop::parse("1,2,3").is_err());
+        assert!(Crop::parse("1,2,0,3").is_err());
+        assert!(Crop::parse("-1,2,3,4").is_err());
+    }
+
+    #[test]
+    fn rejects_regions_past_u32() {
+        assert!(Crop::parse("4294967295,0,1,1") == Ok(Crop::Region(4294967295, 0, 4294967295, 0)));
+        assert!(Crop::parse("4294967295,0,2,1").is_err());
+        assert!(Crop::parse("0,4294967200,1,4294967295").is_err());
+    }
+
+    #[test]
+    fn validates_regions_on_the_board() {
+        let board = GameOfLife::new(10, 8);
+
+        assert!(Crop::Region(9, 7, 20, 20).validate(&board).is_ok());
+        assert!(Crop::Region(10, 0, 12, 2).validate(&board).is_err());
+        assert!(Crop::Region(0, 8, 2, 12).validate(&board).is_err());
+        assert!(Crop::Board.validate(&board).is_ok());
+
+        assert_eq!(Crop::Region(5, 5, 20, 20).region(&board, 0), (5, 5, 9, 7));
+    }
+}
@@ -0,0 +1,222 @@
+// Vector images of the board, for print: one unit of the SVG per cell, cells of the same colour
+// grouped, and boxes, labels and grid lines as outlines and text over them
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::objects::Object;
+use crate::render::{software, Activity, Overlay, Style};
+use crate::GameOfLife;
+use nalgebra_glm as glm;
+
+// Height of the annotation under the board, in cells
+const ANNOTATION_HEIGHT: f32 = 2.0;
+
+pub struct SvgOptions {
+    // Size of a cell in pixels, when the SVG is shown at its natural size
+    pub scale: u32,
+    // Rows of cells of the same colour merged into a path, instead of a rect per cell
+    pub merge: bool,
+    // Generation, rule and topology written under the board
+    pub annotate: bool,
+}
+
+// Draws the cells of `region`, given as (min_x, min_y, max_x, max_y). `activity` must have seen
+// the board
+pub fn export(
+    style: &Style,
+    board: &GameOfLife,
+    activity: &Activity,
+    objects: &[Object],
+    (min_x, min_y, max_x, max_y): (u32, u32, u32, u32),
+    options: &SvgOptions,
+) -> String {
+    let width = max_x - min_x + 1;
+    let height = max_y - min_y + 1;
+    let total_height = height as f32
+        + if options.annotate {
+            ANNOTATION_HEIGHT
+        } else {
+            0.0
+        };
+
+    // Boxes are outlines here, so cells are only coloured by class
+    let mut fill_style = style.clone();
+    if fill_style.overlay != Overlay::Hidden {
+        fill_style.overlay = Overlay::Classes;
+    }
+    let values = fill_style.cell_values(board, activity);
+    let highlights = fill_style.object_highlights(board.width, board.height, objects);
+
+    // Runs of cells of each colour on each row, as (x, y, length), dead cells being the background
+    let dead = hex(style.palette.dead);
+    let mut runs: BTreeMap<String, Vec<(u32, u32, u32)>> = BTreeMap::new();
+
+    for y in 0..height {
+        let mut x = 0;
+
+        while x < width {
+            let color_at = |x: u32| {
+                let index = crate::index(min_x + x, min_y + y, board.width) as usize;
+                hex(software::cell_color(
+                    &fill_style,
+                    values[index],
+                    highlights[index],
+                ))
+            };
+
+            let color = color_at(x);
+            let mut length = 1;
+            while x + length < width && color_at(x + length) == color {
+                length += 1;
+            }
+
+            if color != dead {
+                runs.entry(color).or_default().push((x, y, length));
+            }
+            x += length;
+        }
+    }
+
+    let mut svg = String::new();
+
+    // Writing to a String can't fail
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}" shape-rendering="crispEdges">"#,
+        width * options.scale,
+        total_height * options.scale as f32,
+        width,
+        total_height
+    );
+    let _ = writeln!(
+        svg,
+        r#"<rect width="{}" height="{}" fill="{}"/>"#,
+        width, total_height, dead
+    );
+
+    for (color, runs) in &runs {
+        if options.merge {
+            let mut path = String::new();
+            for &(x, y, length) in runs {
+                let _ = write!(path, "M{} {}h{}v1h-{}z", x, y, length, length);
+            }
+            let _ = writeln!(svg, r#"<path fill="{}" d="{}"/>"#, color, path);
+        } else {
+            let _ = writeln!(svg, r#"<g fill="{}">"#, color);
+            for &(x, y, length) in runs {
+                for x in x..x + length {
+                    let _ = writeln!(svg, r#"<rect x="{}" y="{}" width="1" height="1"/>"#, x, y);
+                }
+            }
+            let _ = writeln!(svg, "</g>");
+        }
+    }
+
+    if style.grid.visible {
+        grid(&mut svg, style, (min_x, min_y), (width, height));
+    }
+
+    if style.overlay == Overlay::Boxes || style.overlay == Overlay::Labels {
+        // Clipped to the board, so boxes don't run over the annotation
+        let _ = writeln!(
+            svg,
+            r#"<svg width="{}" height="{}" overflow="hidden">"#,
+            width, height
+        );
+
+        for object in objects.iter().filter(|object| {
+            object.origin.0 <= max_x
+                && object.origin.1 <= max_y
+                && object.origin.0 + object.size.0 >= min_x
+                && object.origin.1 + object.size.1 >= min_y
+        }) {
+            let (x, y) = (
+                object.origin.0 as f32 - min_x as f32,
+                object.origin.1 as f32 - min_y as f32,
+            );
+            let color = hex(style.palette.objects[object.class.index()]);
+
+            // Around the cells next to the object, as in the window
+            let _ = writeln!(
+                svg,
+                r#"<rect x="{}" y="{}" width="{}" height="{}" fill="none" stroke="{}" stroke-width="0.2"/>"#,
+                x - 0.5,
+                y - 0.5,
+                object.size.0 + 1,
+                object.size.1 + 1,
+                color
+            );
+
+            if style.overlay == Overlay::Labels {
+                let _ = writeln!(
+                    svg,
+                    r#"<text x="{}" y="{}" font-family="sans-serif" font-size="1.2" fill="{}">{}</text>"#,
+                    x - 0.5,
+                    y + object.size.1 as f32 + 1.8,
+                    color,
+                    object.class.label()
+                );
+            }
+        }
+
+        let _ = writeln!(svg, "</svg>");
+    }
+
+    if options.annotate {
+        let _ = writeln!(
+            svg,
+            r#"<text x="0.5" y="{}" font-family="sans-serif" font-size="1.2" fill="{}">Generation {}, rule B3/S23, {}x{} torus</text>"#,
+            height as f32 + 1.4,
+            hex(style.palette.alive),
+            board.generation,
+            board.width,
+            board.height
+        );
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+// Minor lines between all the cells and major lines every `style.grid.major` cells
+fn grid(svg: &mut String, style: &Style, (min_x, min_y): (u32, u32), (width, height): (u32, u32)) {
+    let major = style.grid.major.max(1);
+    let mut minor_path = String::new();
+    let mut major_path = String::new();
+
+    for x in 0..=width {
+        let path = if (min_x + x) % major == 0 {
+            &mut major_path
+        } else {
+            &mut minor_path
+        };
+        let _ = write!(path, "M{} 0v{}", x, height);
+    }
+
+    for y in 0..=height {
+        let path = if (min_y + y) % major == 0 {
+            &mut major_path
+        } else {
+            &mut minor_path
+        };
+        let _ = write!(path, "M0 {}h{}", y, width);
+    }
+
+    let color = hex(style.palette.grid);
+    let _ = writeln!(
+        svg,
+        r#"<path d="{}" stroke="{}" stroke-width="0.05" fill="none"/>"#,
+        minor_path, color
+    );
+    let _ = writeln!(
+        svg,
+        r#"<path d="{}" stroke="{}" stroke-width="0.1" fill="none"/>"#,
+        major_path, color
+    );
+}
+
+fn hex(color: glm::Vec3) -> String {
+    let [red, green, blue] = software::to_rgb(color);
+    format!("#{:02x}{:02x}{:02x}", red, green, blue)
+}
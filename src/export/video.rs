@@ -0,0 +1,163 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use image::imageops::FilterType;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum VideoFormat {
+    // Packed RGB bytes, frame after frame, without any header: ffmpeg needs the size and rate
+    // (-f rawvideo -pixel_format rgb24 -video_size WxH -framerate N)
+    Raw,
+    // YUV4MPEG2, with the size and rate in its header, in 4:4:4 BT.601 limited range
+    Y4m,
+}
+
+impl VideoFormat {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        match text {
+            "raw" | "rgb" => Ok(VideoFormat::Raw),
+            "y4m" => Ok(VideoFormat::Y4m),
+            _ => Err(format!(
+                "Unknown video format \"{}\", expected raw or y4m",
+                text
+            )),
+        }
+    }
+
+    // Format of a file from its name, raw unless it ends with .y4m
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension() {
+            Some(extension) if extension.eq_ignore_ascii_case("y4m") => VideoFormat::Y4m,
+            _ => VideoFormat::Raw,
+        }
+    }
+}
+
+// Uncompressed video written a frame at a time to a file, or to the standard output for "-"
+pub struct VideoWriter {
+    output: Box<dyn Write>,
+    format: VideoFormat,
+    width: u32,
+    height: u32,
+}
+
+impl VideoWriter {
+    pub fn create(
+        path: &Path,
+        format: VideoFormat,
+        (width, height): (u32, u32),
+        frame_rate: u32,
+    ) -> Result<Self, String> {
+        let output: Box<dyn Write> = if path == Path::new("-") {
+            Box::new(BufWriter::new(std::io::stdout()))
+        } else {
+            let file = File::create(path)
+                .map_err(|e| format!("Can't create {}: {}", path.display(), e))?;
+            Box::new(BufWriter::new(file))
+        };
+
+        let mut writer = VideoWriter {
+            output,
+            format,
+            width,
+            height,
+        };
+
+        if format == VideoFormat::Y4m {
+            let header = format!(
+                "YUV4MPEG2 W{} H{} F{}:1 Ip A1:1 C444\n",
+                width, height, frame_rate
+            );
+            writer.write(header.as_bytes())?;
+        }
+
+        Ok(writer)
+    }
+
+    // Adds a frame, which must have the size of the video
+    pub fn add_frame(&mut self, image: &image::RgbaImage) -> Result<(), String> {
+        debug_assert_eq!(image.dimensions(), (self.width, self.height));
+
+        match self.format {
+            VideoFormat::Raw => {
+                let mut rgb = Vec::with_capacity((self.width * self.height * 3) as usize);
+                for pixel in image.pixels() {
+                    rgb.extend_from_slice(&pixel.0[..3]);
+                }
+                self.write(&rgb)
+            }
+            VideoFormat::Y4m => {
+                // Y, then U, then V planes
+                let size = (self.width * self.height) as usize;
+                let mut planes = vec![0u8; size * 3];
+
+                for (index, pixel) in image.pixels().enumerate() {
+                    let (y, u, v) = yuv(pixel.0);
+                    planes[index] = y;
+                    planes[size + index] = u;
+                    planes[2 * size + index] = v;
+                }
+
+                self.write(b"FRAME\n")?;
+                self.write(&planes)
+            }
+        }
+    }
+
+    pub fn finish(mut self) -> Result<(), String> {
+        self.output.flush().map_err(|e| e.to_string())
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), String> {
+        self.output.write_all(bytes).map_err(|e| e.to_string())
+    }
+}
+
+// BT.601 limited range, which ffmpeg assumes for Y4M without a colour range
+fn yuv([r, g, b, _]: [u8; 4]) -> (u8, u8, u8) {
+    let (r, g, b) = (r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0);
+
+    let y = 16.0 + 65.481 * r + 128.553 * g + 24.966 * b;
+    let u = 128.0 - 37.797 * r - 74.203 * g + 112.0 * b;
+    let v = 128.0 + 112.0 * r - 93.786 * g - 18.214 * b;
+
+    (y.round() as u8, u.round() as u8, v.round() as u8)
+}
+
+// Largest whole number of pixels per cell showing `region`, given as
+// (min_x, min_y, max_x, max_y), in a frame of `resolution`, at least 1
+pub fn scale_to_fit(
+    (min_x, min_y, max_x, max_y): (u32, u32, u32, u32),
+    (width, height): (u32, u32),
+) -> u32 {
+    let scale_x = width / (max_x - min_x + 1);
+    let scale_y = height / (max_y - min_y + 1);
+
+    scale_x.min(scale_y).max(1)
+}
+
+// Centres `image` on a black frame of `resolution`, scaling it down first if it doesn't fit
+pub fn fit(image: image::RgbaImage, (width, height): (u32, u32)) -> image::RgbaImage {
+    if image.dimensions() == (width, height) {
+        return image;
+    }
+
+    let image = if image.width() > width || image.height() > height {
+        image::DynamicImage::ImageRgba8(image)
+            .resize(width, height, FilterType::Triangle)
+            .into_rgba8()
+    } else {
+        image
+    };
+
+    let mut frame = image::RgbaImage::from_pixel(width, height, image::Rgba([0, 0, 0, 255]));
+    image::imageops::overlay(
+        &mut frame,
+        &image,
+        (width - image.width()) / 2,
+        (height - image.height()) / 2,
+    );
+
+    frame
+}
@@ -0,0 +1,289 @@
+use crate::shaders;
+use crate::GameOfLife;
+
+// Simulation backend running the Life step on the GPU. The board lives in two single channel
+// textures, one value per cell: each step renders the next generation from one into the other
+// through a framebuffer, and the board is only read back to the CPU when asked for
+pub struct GpuSimulation {
+    width: u32,
+    height: u32,
+    generation: u64,
+    edits: u64,
+    // Whether the textures hold a board yet
+    uploaded: bool,
+    textures: [u32; 2],
+    framebuffers: [u32; 2],
+    // Index of the texture holding the current generation
+    current: usize,
+    shader_program: shaders::ShaderProgram,
+}
+
+impl Drop for GpuSimulation {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteFramebuffers(2, self.framebuffers.as_ptr());
+            gl::DeleteTextures(2, self.textures.as_ptr());
+        }
+    }
+}
+
+impl GpuSimulation {
+    pub fn new(width: u32, height: u32) -> Result<Self, String> {
+        let vertex_shader_source = r"
+            #version 330 core
+            layout(location = 0) in vec3 aPos;
+
+            void main()
+            {
+                // The unit square covers the whole framebuffer, one fragment per cell
+                gl_Position = vec4(aPos.xy * 2.0f - 1.0f, 0.0f, 1.0f);
+            }
+        ";
+
+        // Same rule and torus topology as `GameOfLife::simulate`
+        let fragment_shader_source = r"
+            #version 330 core
+            out vec4 FragColor;
+
+            uniform sampler2D state;
+
+            int alive(ivec2 cell) {
+                ivec2 size = textureSize(state, 0);
+
+                return texelFetch(state, (cell + size) % size, 0).r > 0.5f ? 1 : 0;
+            }
+
+            void main() {
+                ivec2 cell = ivec2(gl_FragCoord.xy);
+
+                int neighbor_count = 0;
+                for (int nj = -1; nj <= 1; nj++) {
+                    for (int ni = -1; ni <= 1; ni++) {
+                        if (ni != 0 || nj != 0) {
+                            neighbor_count += alive(cell + ivec2(ni, nj));
+                        }
+                    }
+                }
+
+                bool next_alive = neighbor_count == 3 || (neighbor_count == 2 && alive(cell) == 1);
+
+                FragColor = vec4(next_alive ? 1.0f : 0.0f, 0.0f, 0.0f, 1.0f);
+            }
+        ";
+
+        let shader_program =
+            shaders::ShaderProgram::new(vertex_shader_source, fragment_shader_source)
+                .map_err(|e| e.to_string())?;
+
+        shader_program.set_uniform("state", 0);
+
+        let mut simulation = GpuSimulation {
+            width,
+            height,
+            generation: 0,
+            edits: 0,
+            uploaded: false,
+            textures: [0; 2],
+            framebuffers: [0; 2],
+            current: 0,
+            shader_program,
+        };
+
+        unsafe {
+            gl::GenTextures(2, simulation.textures.as_mut_ptr());
+            gl::GenFramebuffers(2, simulation.framebuffers.as_mut_ptr());
+
+            for (&texture, &framebuffer) in simulation
+                .textures
+                .iter()
+                .zip(simulation.framebuffers.iter())
+            {
+                gl::BindTexture(gl::TEXTURE_2D, texture);
+
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as i32);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32);
+
+                gl::TexImage2D(
+                    gl::TEXTURE_2D,
+                    0,
+                    gl::R8 as i32,
+                    width as i32,
+                    height as i32,
+                    0,
+                    gl::RED,
+                    gl::UNSIGNED_BYTE,
+                    std::ptr::null(),
+                );
+
+                gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer);
+                gl::FramebufferTexture2D(
+                    gl::FRAMEBUFFER,
+                    gl::COLOR_ATTACHMENT0,
+                    gl::TEXTURE_2D,
+                    texture,
+                    0,
+                );
+
+                let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+                gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+
+                if status != gl::FRAMEBUFFER_COMPLETE {
+                    return Err(format!(
+                        "The simulation framebuffer is incomplete (status 0x{:x})",
+                        status
+                    ));
+                }
+            }
+        }
+
+        Ok(simulation)
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    // Replaces the state on the GPU with `board`, which must have the size of the simulation.
+    // Boards read back from the GPU and not edited since are already there, and aren't uploaded
+    pub fn upload(&mut self, board: &GameOfLife) {
+        debug_assert_eq!((board.width, board.height), (self.width, self.height));
+
+        if self.uploaded && (board.generation, board.edits) == (self.generation, self.edits) {
+            return;
+        }
+
+        // The first board row is the first texture row
+        let cells: Vec<u8> = board
+            .simulation
+            .iter()
+            .map(|&&active| if active { 255 } else { 0 })
+            .collect();
+
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, self.textures[self.current]);
+            gl::TexSubImage2D(
+                gl::TEXTURE_2D,
+                0,
+                0,
+                0,
+                self.width as i32,
+                self.height as i32,
+                gl::RED,
+                gl::UNSIGNED_BYTE,
+                cells.as_ptr() as *const std::ffi::c_void,
+            );
+        }
+
+        self.generation = board.generation;
+        self.edits = board.edits;
+        self.uploaded = true;
+    }
+
+    // Computes the next generation with the unit square in `vao`
+    pub fn step(&mut self, vao: u32) {
+        let next = 1 - self.current;
+
+        unsafe {
+            // The window viewport is restored afterwards
+            let mut viewport = [0; 4];
+            gl::GetIntegerv(gl::VIEWPORT, viewport.as_mut_ptr());
+
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.framebuffers[next]);
+            gl::Viewport(0, 0, self.width as i32, self.height as i32);
+
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindTexture(gl::TEXTURE_2D, self.textures[self.current]);
+
+            gl::UseProgram(self.shader_program.id);
+            gl::BindVertexArray(vao);
+            gl::DrawElements(gl::TRIANGLES, 6, gl::UNSIGNED_INT, std::ptr::null_mut());
+
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            gl::Viewport(viewport[0], viewport[1], viewport[2], viewport[3]);
+        }
+
+        self.current = next;
+        self.generation += 1;
+    }
+
+    // Reads the current generation back from the GPU. This waits for all the steps queued so far
+    pub fn read(&self) -> GameOfLife<'static> {
+        let mut cells = vec![0u8; (self.width * self.height) as usize];
+
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.framebuffers[self.current]);
+            gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+            gl::ReadPixels(
+                0,
+                0,
+                self.width as i32,
+                self.height as i32,
+                gl::RED,
+                gl::UNSIGNED_BYTE,
+                cells.as_mut_ptr() as *mut std::ffi::c_void,
+            );
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+
+        GameOfLife {
+            width: self.width,
+            height: self.height,
+            generation: self.generation,
+            edits: self.edits,
+            simulation: cells
+                .iter()
+                .map(|&cell| if cell != 0 { &true } else { &false })
+                .collect(),
+        }
+    }
+}
+
+// Steps `board` for `generations` on the GPU and with `GameOfLife::simulate`, comparing every
+// generation. A small board with patterns across its edges and corners is checked the same way,
+// so the wrap-around is covered whatever `board` is
+pub fn check(board: &GameOfLife, generations: u64, vao: u32) -> Result<(), String> {
+    let mut edges = GameOfLife::new(13, 7);
+
+    // A glider over the bottom right corner
+    let glider = crate::pattern::rle::decode("bo$2bo$3o!", (3, 3))?;
+    edges.paste(&glider, (11, 5));
+    // Blinkers across the left and right edges, and across the top and bottom ones
+    for &(x, y) in &[(12, 3), (0, 3), (1, 3), (6, 6), (6, 0), (6, 1)] {
+        edges.set(x, y, true);
+    }
+
+    compare(board, generations, vao)?;
+    compare(&edges, generations, vao)
+}
+
+fn compare(board: &GameOfLife, generations: u64, vao: u32) -> Result<(), String> {
+    let mut gpu = GpuSimulation::new(board.width, board.height)?;
+    gpu.upload(board);
+
+    let mut cpu = board.simulate();
+
+    for _ in 0..generations {
+        gpu.step(vao);
+        let read = gpu.read();
+
+        let differences = read
+            .simulation
+            .iter()
+            .zip(&cpu.simulation)
+            .filter(|(gpu_cell, cpu_cell)| gpu_cell != cpu_cell)
+            .count();
+
+        if differences > 0 {
+            return Err(format!(
+                "Generation {} of the {}x{} board differs on {} cells between the GPU and the CPU",
+                cpu.generation, board.width, board.height, differences
+            ));
+        }
+
+        cpu = cpu.simulate();
+    }
+
+    Ok(())
+}
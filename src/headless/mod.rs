@@ -0,0 +1,121 @@
+use crate::export::gif::GifRecorder;
+use crate::export::svg;
+use crate::export::video::{self, VideoFormat, VideoWriter};
+use crate::objects::Classifier;
+use crate::options::Options;
+use crate::render::{software, Activity, Renderer, Scene, SoftwareRenderer};
+use crate::statistics::record;
+use crate::GameOfLife;
+
+// Runs the simulation without a window, and writes the exports and statistics asked for on the
+// command line
+pub fn run(options: &Options, board: GameOfLife) -> Result<(), String> {
+    let mut board = board;
+    let mut activity = Activity::new(board.width, board.height);
+    activity.observe(&board);
+
+    let mut classifier = Classifier::new();
+    let mut statistics = options.statistics()?;
+    record(&mut statistics, &mut classifier, &board)?;
+
+    for _ in 0..options.generations {
+        board = board.simulate();
+        activity.observe(&board);
+        record(&mut statistics, &mut classifier, &board)?;
+    }
+
+    let style = options.style();
+
+    if let Some(path) = &options.screenshot {
+        let objects = classifier.find_objects(&board);
+        let mut renderer =
+            SoftwareRenderer::new(style.clone(), options.crop.region(&board, 0), options.scale);
+        renderer.render(&Scene {
+            board: &board,
+            activity: &activity,
+            objects: &objects,
+            selection: None,
+        });
+
+        renderer
+            .read_pixels()
+            .save(path)
+            .map_err(|e| format!("Can't save {}: {}", path.display(), e))?;
+    }
+
+    if let Some(path) = &options.svg {
+        let objects = classifier.find_objects(&board);
+        let text = svg::export(
+            &style,
+            &board,
+            &activity,
+            &objects,
+            options.crop.region(&board, 0),
+            &options.svg_options(),
+        );
+
+        std::fs::write(path, text).map_err(|e| format!("Can't save {}: {}", path.display(), e))?;
+    }
+
+    // The rest records the next generations
+    if options.gif.is_none() && options.video.is_none() {
+        return Ok(());
+    }
+
+    let mut gif = match &options.gif {
+        Some(path) => Some(GifRecorder::create(path, options.delay)?),
+        None => None,
+    };
+
+    let generations = (options.frames - 1) * options.steps_per_frame;
+    let region = options.crop.region(&board, generations);
+
+    let mut video = match &options.video {
+        Some(path) => {
+            let scale = match options.resolution {
+                Some(resolution) => video::scale_to_fit(region, resolution),
+                None => options.scale,
+            };
+            // The size of the board at --scale unless a resolution is given
+            let resolution = options.resolution.unwrap_or((
+                (region.2 - region.0 + 1) * scale,
+                (region.3 - region.1 + 1) * scale,
+            ));
+            let format = options
+                .video_format
+                .unwrap_or_else(|| VideoFormat::from_path(path));
+
+            let writer = VideoWriter::create(path, format, resolution, options.frame_rate)?;
+            Some((writer, scale, resolution))
+        }
+        None => None,
+    };
+
+    for frame in 0..options.frames {
+        if frame > 0 {
+            for _ in 0..options.steps_per_frame {
+                board = board.simulate();
+                activity.observe(&board);
+                record(&mut statistics, &mut classifier, &board)?;
+            }
+        }
+
+        let objects = classifier.find_objects(&board);
+        let render =
+            |scale| software::render(&style, &board, &activity, &objects, None, region, scale);
+
+        if let Some(gif) = &mut gif {
+            gif.add_frame(render(options.scale))?;
+        }
+
+        if let Some((writer, scale, resolution)) = &mut video {
+            writer.add_frame(&video::fit(render(*scale), *resolution))?;
+        }
+    }
+
+    if let Some((writer, _, _)) = video {
+        writer.finish()?;
+    }
+
+    Ok(())
+}
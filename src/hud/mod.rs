@@ -0,0 +1,324 @@
+use crate::shaders;
+use crate::Texture;
+use nalgebra_glm as glm;
+
+mod font;
+
+const COLUMNS: u32 = 44;
+// Lines the canvas has room for at first, it grows when more are drawn
+const LINES: u32 = 18;
+// Longest label drawn over the board
+const LABEL_COLUMNS: u32 = 24;
+// Different label texts the label canvas has room for, in rows of LABEL_SLOTS_ACROSS slots
+const LABEL_SLOTS: u32 = 64;
+const LABEL_SLOTS_ACROSS: u32 = 4;
+
+// Size of a character cell on the HUD canvas, including spacing
+const CHAR_WIDTH: u32 = font::GLYPH_WIDTH + 1;
+const LINE_HEIGHT: u32 = font::GLYPH_HEIGHT + 3;
+const PADDING: u32 = 4;
+
+// Size of a label slot on the label canvas
+const LABEL_WIDTH: u32 = LABEL_COLUMNS * CHAR_WIDTH + 2 * PADDING;
+const LABEL_HEIGHT: u32 = LINE_HEIGHT + 2 * PADDING;
+
+// Each canvas pixel is drawn as SCALE x SCALE window points, LABEL_SCALE for labels
+const SCALE: u32 = 2;
+const LABEL_SCALE: u32 = 1;
+const MARGIN: u32 = 8;
+
+const BACKGROUND: [u8; 3] = [32, 32, 32];
+const FOREGROUND: [u8; 3] = [230, 230, 230];
+
+// Text overlay in the top left corner of the window, and labels drawn over the board. The text is
+// rasterized on the CPU into a small RGB canvas, which is drawn as a textured quad
+pub struct Hud {
+    pub visible: bool,
+    // Lines of text the canvas has room for
+    lines: u32,
+    canvas: Vec<u8>,
+    texture: Texture,
+    label_canvas: Vec<u8>,
+    label_texture: Texture,
+    // Texts rasterized on the label canvas, by slot, so it is only uploaded when they change
+    label_texts: Vec<String>,
+    shader_program: shaders::ShaderProgram,
+}
+
+// Canvas of `lines` lines of text, and its texture
+fn text_canvas(lines: u32) -> (Vec<u8>, Texture) {
+    let width = COLUMNS * CHAR_WIDTH + 2 * PADDING;
+    let height = lines * LINE_HEIGHT + 2 * PADDING;
+
+    let canvas = vec![0; (width * height * 3) as usize];
+    let texture = Texture::new(canvas.as_ptr(), width, height);
+
+    (canvas, texture)
+}
+
+// Top left corner of a label slot on the label canvas
+fn label_slot(slot: u32) -> (u32, u32) {
+    (
+        slot % LABEL_SLOTS_ACROSS * LABEL_WIDTH,
+        slot / LABEL_SLOTS_ACROSS * LABEL_HEIGHT,
+    )
+}
+
+impl Hud {
+    pub fn new() -> Result<Self, shaders::ShaderError> {
+        let (canvas, texture) = text_canvas(LINES);
+
+        let label_width = LABEL_SLOTS_ACROSS * LABEL_WIDTH;
+        let label_height = (LABEL_SLOTS / LABEL_SLOTS_ACROSS) * LABEL_HEIGHT;
+
+        let label_canvas = vec![0; (label_width * label_height * 3) as usize];
+        let label_texture = Texture::new(label_canvas.as_ptr(), label_width, label_height);
+
+        let vertex_shader_source = r"
+            #version 330 core
+            layout(location = 0) in vec3 aPos;
+            layout(location = 1) in vec2 aTexCoord;
+
+            out vec2 TexCoord;
+
+            // x, y, width, height of the overlay in normalized device coordinates
+            uniform vec4 rect;
+            // x, y, width, height of the part of the canvas that is drawn, in fractions of the
+            // canvas from its top left
+            uniform vec4 source;
+
+            void main()
+            {
+                gl_Position = vec4(rect.xy + aPos.xy * rect.zw, 0.0f, 1.0f);
+
+                // The first canvas row is the top of the overlay
+                TexCoord = source.xy + vec2(aTexCoord.x, 1.0f - aTexCoord.y) * source.zw;
+            }
+        ";
+
+        let fragment_shader_source = r"
+            #version 330 core
+            out vec4 FragColor;
+
+            in vec2 TexCoord;
+
+            uniform sampler2D tex;
+
+            void main() {
+                FragColor = texture(tex, TexCoord);
+            }
+        ";
+
+        let shader_program =
+            shaders::ShaderProgram::new(vertex_shader_source, fragment_shader_source)?;
+
+        shader_program.set_uniform("tex", 0);
+
+        Ok(Hud {
+            visible: true,
+            lines: LINES,
+            canvas,
+            texture,
+            label_canvas,
+            label_texture,
+            label_texts: Vec::new(),
+            shader_program,
+        })
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    // Draws the lines of text, then the legend: lines of text after a square of their colour.
+    // The unit square in `vao` is used for the quad. `pixel_ratio` is the number of viewport
+    // pixels per window point, above 1 on HiDPI displays
+    pub fn draw(
+        &mut self,
+        lines: &[String],
+        legend: &[([u8; 3], String)],
+        vao: u32,
+        viewport: (u32, u32),
+        pixel_ratio: f32,
+    ) {
+        let used_lines = (lines.len() + legend.len()) as u32;
+        if used_lines > self.lines {
+            let (canvas, texture) = text_canvas(used_lines);
+            self.lines = used_lines;
+            self.canvas = canvas;
+            self.texture = texture;
+        }
+
+        let width = self.texture.width;
+        fill(&mut self.canvas, BACKGROUND);
+
+        let legend_lines = legend.iter().map(|(_, text)| text);
+
+        for (line, text) in lines.iter().chain(legend_lines).enumerate() {
+            let top = PADDING + line as u32 * LINE_HEIGHT;
+
+            if line < lines.len() {
+                write(&mut self.canvas, width, text, PADDING, top, COLUMNS);
+            } else {
+                let (colour, _) = &legend[line - lines.len()];
+
+                for y in 0..font::GLYPH_HEIGHT {
+                    for x in 0..font::GLYPH_HEIGHT {
+                        let index = crate::index(PADDING + x, top + y, width) as usize;
+                        self.canvas[index * 3..index * 3 + 3].copy_from_slice(colour);
+                    }
+                }
+
+                let left = PADDING + 2 * CHAR_WIDTH;
+                write(&mut self.canvas, width, text, left, top, COLUMNS - 2);
+            }
+        }
+
+        self.texture.update(self.canvas.as_ptr());
+
+        // Only the lines in use are drawn, so the overlay fits the text
+        let used_height = used_lines * LINE_HEIGHT + 2 * PADDING;
+
+        let margin = scaled(MARGIN, pixel_ratio);
+        self.draw_canvas(
+            &self.texture,
+            (0, 0, width, used_height),
+            (margin, margin),
+            scaled(SCALE, pixel_ratio),
+            vao,
+            viewport,
+        );
+    }
+
+    // Draws lines of text with their top left corner at the given positions, in viewport pixels.
+    // Each different text is rasterized once in a slot of the label canvas, and labels past the
+    // last slot aren't drawn
+    pub fn draw_labels(
+        &mut self,
+        labels: &[(String, (u32, u32))],
+        vao: u32,
+        viewport: (u32, u32),
+        pixel_ratio: f32,
+    ) {
+        let mut texts: Vec<&str> = Vec::new();
+        for (text, _) in labels {
+            if texts.len() < LABEL_SLOTS as usize && !texts.contains(&text.as_str()) {
+                texts.push(text);
+            }
+        }
+
+        if self
+            .label_texts
+            .iter()
+            .map(String::as_str)
+            .ne(texts.iter().copied())
+        {
+            let width = self.label_texture.width;
+            fill(&mut self.label_canvas, BACKGROUND);
+
+            for (slot, text) in texts.iter().enumerate() {
+                let (left, top) = label_slot(slot as u32);
+                write(
+                    &mut self.label_canvas,
+                    width,
+                    text,
+                    left + PADDING,
+                    top + PADDING,
+                    LABEL_COLUMNS,
+                );
+            }
+
+            self.label_texture.update(self.label_canvas.as_ptr());
+            self.label_texts = texts.iter().map(|text| text.to_string()).collect();
+        }
+
+        for (text, position) in labels {
+            let slot = match self
+                .label_texts
+                .iter()
+                .position(|slot_text| slot_text == text)
+            {
+                Some(slot) => slot as u32,
+                None => continue,
+            };
+
+            let (left, top) = label_slot(slot);
+            let used_columns = (text.chars().count() as u32).min(LABEL_COLUMNS);
+            let used_width = used_columns * CHAR_WIDTH + 2 * PADDING;
+
+            self.draw_canvas(
+                &self.label_texture,
+                (left, top, used_width, LABEL_HEIGHT),
+                *position,
+                scaled(LABEL_SCALE, pixel_ratio),
+                vao,
+                viewport,
+            );
+        }
+    }
+
+    // Draws the `used` rectangle of a canvas texture, left, top, width and height in canvas
+    // pixels, with its top left corner at `position`, in viewport pixels
+    fn draw_canvas(
+        &self,
+        texture: &Texture,
+        (used_left, used_top, used_width, used_height): (u32, u32, u32, u32),
+        (x, y): (u32, u32),
+        scale: u32,
+        vao: u32,
+        (viewport_width, viewport_height): (u32, u32),
+    ) {
+        let width = 2.0 * (used_width * scale) as f32 / viewport_width as f32;
+        let height = 2.0 * (used_height * scale) as f32 / viewport_height as f32;
+        let left = -1.0 + 2.0 * x as f32 / viewport_width as f32;
+        let top = 1.0 - 2.0 * y as f32 / viewport_height as f32;
+
+        self.shader_program
+            .set_uniform("rect", glm::vec4(left, top - height, width, height));
+        self.shader_program.set_uniform(
+            "source",
+            glm::vec4(
+                used_left as f32 / texture.width as f32,
+                used_top as f32 / texture.height as f32,
+                used_width as f32 / texture.width as f32,
+                used_height as f32 / texture.height as f32,
+            ),
+        );
+
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindTexture(gl::TEXTURE_2D, texture.id);
+
+            gl::UseProgram(self.shader_program.id);
+            gl::BindVertexArray(vao);
+            gl::DrawElements(gl::TRIANGLES, 6, gl::UNSIGNED_INT, std::ptr::null_mut());
+        }
+    }
+}
+
+// Window points in viewport pixels, rounded to whole pixels so the text stays sharp
+fn scaled(points: u32, pixel_ratio: f32) -> u32 {
+    (points as f32 * pixel_ratio).round().max(1.0) as u32
+}
+
+fn fill(canvas: &mut [u8], colour: [u8; 3]) {
+    for pixel in canvas.chunks_mut(3) {
+        pixel.copy_from_slice(&colour);
+    }
+}
+
+// Rasterizes at most `columns` characters of text with its top left corner at (left, top)
+fn write(canvas: &mut [u8], width: u32, text: &str, left: u32, top: u32, columns: u32) {
+    for (column, c) in text.chars().take(columns as usize).enumerate() {
+        let left = left + column as u32 * CHAR_WIDTH;
+
+        for (row, bits) in font::glyph(c).iter().enumerate() {
+            for i in 0..font::GLYPH_WIDTH {
+                if bits & (1 << (font::GLYPH_WIDTH - 1 - i)) != 0 {
+                    let index = crate::index(left + i, top + row as u32, width) as usize;
+                    canvas[index * 3..index * 3 + 3].copy_from_slice(&FOREGROUND);
+                }
+            }
+        }
+    }
+}
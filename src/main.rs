@@ -1,12 +1,29 @@
+use crate::render::Renderer;
+use nalgebra_glm as glm;
 use rand::prelude::*;
-use sdl2::event::Event;
-use sdl2::keyboard::Keycode;
-use sdl2::video::GLProfile;
-
+use sdl2::event::{Event, WindowEvent};
+use sdl2::keyboard::{Keycode, Mod};
+use sdl2::mouse::MouseButton;
+use sdl2::video::{FullscreenType, GLProfile, Window};
+use std::time::{Duration, Instant};
+
+mod camera;
+mod export;
+mod gpu;
+mod headless;
+mod hud;
+mod objects;
+mod options;
+mod pattern;
+mod playback;
+mod render;
+mod selection;
 mod shaders;
+mod statistics;
+mod terminal;
+mod undo;
 
 // Notes :
-// - textures are upside down, probably filled the wrong way? could be flipped with opengl?
 // - could use a ring of game of life buffers to do the simulation and not having to reallocate a vec<bool> at every frame
 // - generalize into cellular automaton structures
 
@@ -16,6 +33,14 @@ struct Texture {
     height: u32,
 }
 
+impl Drop for Texture {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteTextures(1, &self.id);
+        }
+    }
+}
+
 impl Texture {
     fn new(data: *const u8, width: u32, height: u32) -> Self {
         let texture = unsafe {
@@ -72,9 +97,16 @@ impl Texture {
     }
 }
 
+// Board coordinates, shared by the simulation, the pattern importers, mouse picking and the
+// renderer: cell (0, 0) is the top left corner, x grows to the right and y grows downwards, as in
+// Golly and RLE files. Cells are stored row by row from the top, which is also the order of the
+// texture rows, with texture coordinate (0, 0) on the top left corner of the board
 struct GameOfLife<'a> {
     width: u32,
     height: u32,
+    generation: u64,
+    // Number of cells changed by edits, so views can tell an edited board from the one they show
+    edits: u64,
     simulation: Vec<&'a bool>,
 }
 
@@ -90,6 +122,8 @@ impl GameOfLife<'_> {
         GameOfLife {
             width,
             height,
+            generation: 0,
+            edits: 0,
             simulation: vec![&false; (width * height) as usize],
         }
     }
@@ -98,6 +132,151 @@ impl GameOfLife<'_> {
         self.simulation[index(x, y, self.width) as usize]
     }
 
+    fn set(&mut self, x: u32, y: u32, value: bool) -> Option<undo::CellChange> {
+        let before = *self.at(x, y);
+
+        self.simulation[index(x, y, self.width) as usize] = if value { &true } else { &false };
+
+        if before == value {
+            return None;
+        }
+
+        self.edits += 1;
+
+        Some(undo::CellChange {
+            x,
+            y,
+            before,
+            after: value,
+        })
+    }
+
+    // Fills the board with a random soup of the given density
+    fn reseed(&mut self, rng: &mut impl Rng, density: f32) -> Vec<undo::CellChange> {
+        let mut changes = Vec::new();
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                changes.extend(self.set(x, y, rng.gen::<f32>() < density));
+            }
+        }
+
+        changes
+    }
+
+    fn population(&self) -> usize {
+        self.simulation.iter().filter(|&&&cell| cell).count()
+    }
+
+    // Cells born and cells that died since `previous`
+    fn changes_since(&self, previous: &GameOfLife) -> (usize, usize) {
+        self.simulation.iter().zip(&previous.simulation).fold(
+            (0, 0),
+            |(births, deaths), (&&cell, &&previous_cell)| match (previous_cell, cell) {
+                (false, true) => (births + 1, deaths),
+                (true, false) => (births, deaths + 1),
+                _ => (births, deaths),
+            },
+        )
+    }
+
+    // Bounding box of the live cells, as (min_x, min_y, max_x, max_y)
+    fn population_bounds(&self) -> Option<(u32, u32, u32, u32)> {
+        let mut bounds: Option<(u32, u32, u32, u32)> = None;
+
+        for (index, &&cell) in self.simulation.iter().enumerate() {
+            if cell {
+                let x = index as u32 % self.width;
+                let y = index as u32 / self.width;
+                bounds = Some(match bounds {
+                    Some((min_x, min_y, max_x, max_y)) => {
+                        (min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y))
+                    }
+                    None => (x, y, x, y),
+                });
+            }
+        }
+
+        bounds
+    }
+
+    // Average position of the live cells
+    fn population_center(&self) -> Option<(f32, f32)> {
+        let mut sum = (0.0, 0.0);
+        let mut population = 0;
+
+        for (index, &&cell) in self.simulation.iter().enumerate() {
+            if cell {
+                sum.0 += (index as u32 % self.width) as f32;
+                sum.1 += (index as u32 / self.width) as f32;
+                population += 1;
+            }
+        }
+
+        if population == 0 {
+            return None;
+        }
+
+        Some((sum.0 / population as f32, sum.1 / population as f32))
+    }
+
+    // Copies a rectangle of the board, given as (min_x, min_y, max_x, max_y)
+    fn copy_region(&self, (min_x, min_y, max_x, max_y): (u32, u32, u32, u32)) -> pattern::Pattern {
+        let mut pattern = pattern::Pattern::new(max_x - min_x + 1, max_y - min_y + 1);
+
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                pattern.set(x - min_x, y - min_y, *self.at(x, y));
+            }
+        }
+
+        pattern
+    }
+
+    fn clear_region(
+        &mut self,
+        (min_x, min_y, max_x, max_y): (u32, u32, u32, u32),
+    ) -> Vec<undo::CellChange> {
+        let mut changes = Vec::new();
+
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                changes.extend(self.set(x, y, false));
+            }
+        }
+
+        changes
+    }
+
+    // Board cell under a pattern cell when the top left corner of the pattern is on `origin`,
+    // wrapping around the edges of the board
+    fn pattern_cell(&self, (origin_x, origin_y): (u32, u32), x: u32, y: u32) -> (u32, u32) {
+        ((origin_x + x) % self.width, (origin_y + y) % self.height)
+    }
+
+    // Overwrites the cells under a pattern with the pattern
+    fn paste(&mut self, pattern: &pattern::Pattern, origin: (u32, u32)) -> Vec<undo::CellChange> {
+        let mut changes = Vec::new();
+
+        for y in 0..pattern.height {
+            for x in 0..pattern.width {
+                let (cell_x, cell_y) = self.pattern_cell(origin, x, y);
+                changes.extend(self.set(cell_x, cell_y, pattern.at(x, y)));
+            }
+        }
+
+        changes
+    }
+
+    fn edit(&mut self, stroke: Stroke, x: u32, y: u32) -> Option<undo::CellChange> {
+        let value = match stroke {
+            Stroke::Set => true,
+            Stroke::Clear => false,
+            Stroke::Toggle => !*self.at(x, y),
+        };
+        self.set(x, y, value)
+    }
+
     fn simulate(&self) -> Self {
         let mut new_simulation = vec![&false; (self.width * self.height) as usize];
 
@@ -107,8 +286,8 @@ impl GameOfLife<'_> {
                 // Count all neighbors for current simulation
                 let mut neighbor_count = 0;
 
-                for nj in -1 as i32..=1 {
-                    for ni in -1 as i32..=1 {
+                for nj in -1..=1 {
+                    for ni in -1..=1 {
                         if ni == 0 && nj == 0 {
                             continue;
                         }
@@ -139,14 +318,273 @@ impl GameOfLife<'_> {
         GameOfLife {
             width: self.width,
             height: self.height,
+            generation: self.generation + 1,
+            edits: self.edits,
             simulation: new_simulation,
         }
     }
 }
 
+#[derive(Clone, Copy, PartialEq)]
+enum EditMode {
+    Draw,
+    Toggle,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Stroke {
+    Set,
+    Clear,
+    Toggle,
+}
+
+impl Stroke {
+    fn name(self) -> &'static str {
+        match self {
+            Stroke::Set => "Draw",
+            Stroke::Clear => "Erase",
+            Stroke::Toggle => "Toggle",
+        }
+    }
+}
+
+fn ctrl(keymod: Mod) -> bool {
+    keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD)
+}
+
+fn shift(keymod: Mod) -> bool {
+    keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD)
+}
+
+fn alt(keymod: Mod) -> bool {
+    keymod.intersects(Mod::LALTMOD | Mod::RALTMOD)
+}
+
+// Screenshots taken at the end of the frame
+#[derive(Clone, Copy)]
+enum Screenshot {
+    // One pixel per cell, or more with --scale, whatever the view
+    Board,
+    // The window as drawn
+    View,
+}
+
+// GIF recorded from the window with F10
+struct Recording {
+    recorder: export::gif::GifRecorder,
+    // Cells shown, as (min_x, min_y, max_x, max_y)
+    region: (u32, u32, u32, u32),
+    // Generation of the next frame
+    next_generation: u64,
+    frames: u64,
+}
+
+#[derive(Clone, Copy)]
+enum Transform {
+    RotateClockwise,
+    RotateCounterClockwise,
+    FlipHorizontal,
+    FlipVertical,
+}
+
+impl Transform {
+    fn name(self) -> &'static str {
+        match self {
+            Transform::RotateClockwise | Transform::RotateCounterClockwise => "Rotate",
+            Transform::FlipHorizontal | Transform::FlipVertical => "Flip",
+        }
+    }
+
+    fn apply(self, pattern: &pattern::Pattern) -> pattern::Pattern {
+        match self {
+            Transform::RotateClockwise => pattern.rotate_clockwise(),
+            Transform::RotateCounterClockwise => pattern.rotate_counter_clockwise(),
+            Transform::FlipHorizontal => pattern.flip_horizontal(),
+            Transform::FlipVertical => pattern.flip_vertical(),
+        }
+    }
+}
+
+// Converts a position in window coordinates to drawable pixels, which are smaller on HiDPI displays
+fn window_to_pixels(window: &Window, x: f32, y: f32) -> (f32, f32) {
+    let (window_width, window_height) = window.size();
+    let (drawable_width, drawable_height) = window.drawable_size();
+
+    (
+        x * drawable_width as f32 / window_width as f32,
+        y * drawable_height as f32 / window_height as f32,
+    )
+}
+
+// Maps a position in window coordinates (origin top left, as reported by SDL) to the board cell
+// under it: window -> viewport pixels -> world (through the camera) -> texture coordinates -> cell
+fn window_to_cell(
+    x: i32,
+    y: i32,
+    camera: &camera::Camera,
+    window: &Window,
+    (board_width, board_height): (u32, u32),
+) -> Option<(u32, u32)> {
+    // Sample at the pixel centre so cell borders land where they are drawn
+    let (x, y) = window_to_pixels(window, x as f32 + 0.5, y as f32 + 0.5);
+    let world = camera.window_to_world(x, y, window.drawable_size());
+
+    // The board quad maps texture coordinates (0, 0)-(1, 1) onto the world rectangle
+    // (0, 0)-(board_width, board_height)
+    let u = world.x / board_width as f32;
+    let v = world.y / board_height as f32;
+
+    if !(0.0..1.0).contains(&u) || !(0.0..1.0).contains(&v) {
+        return None;
+    }
+
+    Some((
+        (u * board_width as f32) as u32,
+        (v * board_height as f32) as u32,
+    ))
+}
+
+// All the cells on the line between two cells (Bresenham), so fast drags don't leave gaps
+fn line_cells((x0, y0): (u32, u32), (x1, y1): (u32, u32)) -> Vec<(u32, u32)> {
+    let (mut x, mut y) = (x0 as i32, y0 as i32);
+    let (x1, y1) = (x1 as i32, y1 as i32);
+
+    let dx = (x1 - x).abs();
+    let dy = -(y1 - y).abs();
+    let step_x = if x < x1 { 1 } else { -1 };
+    let step_y = if y < y1 { 1 } else { -1 };
+    let mut error = dx + dy;
+
+    let mut cells = vec![(x as u32, y as u32)];
+
+    while x != x1 || y != y1 {
+        let error2 = 2 * error;
+        if error2 >= dy {
+            error += dy;
+            x += step_x;
+        }
+        if error2 <= dx {
+            error += dx;
+            y += step_y;
+        }
+        cells.push((x as u32, y as u32));
+    }
+
+    cells
+}
+
+// Prints a message, on the standard error when the statistics are written to the standard output
+fn report(options: &options::Options, message: &str) {
+    if options.stats_to_stdout() {
+        eprintln!("{}", message);
+    } else {
+        println!("{}", message);
+    }
+}
+
+// Records `board` in the statistics, which stop when they can't be written
+fn record_statistics(
+    statistics: &mut Option<statistics::Statistics>,
+    classifier: &mut objects::Classifier,
+    board: &GameOfLife,
+) {
+    if let Err(e) = statistics::record(statistics, classifier, board) {
+        eprintln!("Can't write statistics: {}", e);
+        *statistics = None;
+    }
+}
+
 pub fn main() -> Result<(), String> {
+    let options = options::Options::parse(std::env::args().skip(1))?;
+
+    if options.help {
+        println!("{}", options::USAGE);
+        return Ok(());
+    }
+
     let mut rng = rand::thread_rng();
 
+    let mut game_of_life = GameOfLife::new(100, 100);
+
+    if let Some(path) = &options.image {
+        let pattern = pattern::bitmap::import(
+            path,
+            (game_of_life.width, game_of_life.height),
+            options.conversion,
+            options.invert,
+        )
+        .map_err(|e| format!("Can't import {}: {}", path.display(), e))?;
+
+        // Centred on the board
+        let origin = (
+            (game_of_life.width - pattern.width) / 2,
+            (game_of_life.height - pattern.height) / 2,
+        );
+        game_of_life.paste(&pattern, origin);
+    } else {
+        game_of_life.simulation = game_of_life
+            .simulation
+            .iter()
+            .map(|_| {
+                if rng.gen::<f32>() < 0.3 {
+                    &true
+                } else {
+                    &false
+                }
+            })
+            .collect();
+    }
+
+    // game_of_life.simulation = vec![
+    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
+    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
+    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
+    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
+    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
+    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
+    //     &false, &false, &false, &true, &false, &false, &false, &false, &false, &false, &false,
+    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
+    //     &false, &false, &true, &false, &false, &false, &false, &false, &false, &false, &false,
+    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &true, &true,
+    //     &true, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
+    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
+    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
+    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
+    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
+    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
+    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
+    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
+    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
+    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
+    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
+    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
+    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
+    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
+    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
+    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
+    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
+    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
+    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
+    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
+    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
+    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
+    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
+    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
+    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
+    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
+    //     &false, &false, &false, &false,
+    // ];
+
+    options.crop.validate(&game_of_life)?;
+
+    if options.headless() {
+        return headless::run(&options, game_of_life);
+    }
+
+    if options.terminal {
+        return terminal::run(&options, game_of_life);
+    }
+
     let sdl_context = sdl2::init()?;
     let video_subsystem = sdl_context.video()?;
 
@@ -154,29 +592,44 @@ pub fn main() -> Result<(), String> {
     gl_attr.set_context_profile(GLProfile::Core);
     gl_attr.set_context_version(3, 3);
 
-    let window = video_subsystem
-        .window("Game of life", 800, 600)
+    let clipboard = video_subsystem.clipboard();
+
+    let mut window_builder = video_subsystem.window("Game of life", 800, 600);
+    window_builder
         .position_centered()
         .opengl()
         .position_centered()
-        .build()
-        .map_err(|e| e.to_string())?;
+        .resizable()
+        .allow_highdpi();
+
+    // Only the OpenGL context is needed to check the GPU simulation
+    if options.gpu_check.is_some() {
+        window_builder.hidden();
+    }
+
+    let mut window = window_builder.build().map_err(|e| e.to_string())?;
 
     let _gl = window.gl_create_context().unwrap();
 
     gl::load_with(|name| video_subsystem.gl_get_proc_address(name) as *const _);
 
+    // Texture rows are tightly packed RGB, whatever their width
+    unsafe {
+        gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+    }
+
     debug_assert_eq!(gl_attr.context_profile(), GLProfile::Core);
     debug_assert_eq!(gl_attr.context_version(), (3, 3));
 
     let mut event_pump = sdl_context.event_pump().unwrap();
 
+    // Unit square, scaled to the board size by the model matrix
     let rectangle: Vec<f32> = vec![
-        -1.0, 1.0, 0.0, 0.0, 1.0, 1.0, 1.0, 0.0, 1.0, 1.0, -1.0, -1.0, 0.0, 0.0, 0.0, 1.0, -1.0,
-        0.0, 1.0, 0.0,
+        0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 1.0, 0.0, 1.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0,
+        1.0, 0.0,
     ];
 
-    let indices = vec![0, 1, 2, 2, 1, 3];
+    let indices = [0, 1, 2, 2, 1, 3];
 
     let vao = unsafe {
         let mut vao = 0;
@@ -231,190 +684,124 @@ pub fn main() -> Result<(), String> {
         gl::EnableVertexAttribArray(1);
     }
 
-    let vertex_shader_source = r"
-    #version 330 core
-    layout(location = 0) in vec3 aPos;
-    
-
-    void main()
-    {
-        gl_Position = vec4(aPos.x, aPos.y, aPos.z, 1.0f);
-    }
-";
-
-    let fragment_shader_source = r"
-    #version 330 core
-    out vec4 FragColor;
-
-    void main() {
-        FragColor = vec4(0.8, 0.2, 0.7, 1.0);
+    if let Some(generations) = options.gpu_check {
+        gpu::check(&game_of_life, generations, vao)?;
+        println!("The GPU and the CPU agree on {} generations", generations);
+        return Ok(());
     }
-";
 
-    let color_shader = shaders::ShaderProgram::new(vertex_shader_source, fragment_shader_source);
-
-    let vertex_shader_source = r"
-        #version 330 core
-        layout(location = 0) in vec3 aPos;
-        layout(location = 1) in vec2 aTexCoord;
-        
-        out vec2 TexCoord;
-
-        void main()
-        {
-            gl_Position = vec4(aPos.x, aPos.y, aPos.z, 1.0f);
-
-            TexCoord = aTexCoord;
-        }
-    ";
-
-    let fragment_shader_source = r"
-        #version 330 core
-        out vec4 FragColor;
-
-        in vec2 TexCoord;
-
-        uniform sampler2D tex;
-
-        void main() {
-            FragColor = texture(tex, TexCoord);
-        }
-    ";
-
-    let shader_program = shaders::ShaderProgram::new(vertex_shader_source, fragment_shader_source);
-
-    unsafe {
-        let t = std::ffi::CString::new("tex").unwrap();
-
-        gl::Uniform1i(gl::GetUniformLocation(shader_program.id, t.as_ptr()), 0);
-    }
-
-    let mut game_of_life = GameOfLife::new(100, 100);
-
-    game_of_life.simulation = game_of_life
-        .simulation
-        .iter()
-        .map(|_| {
-            if rng.gen::<f32>() < 0.3 {
-                &true
-            } else {
-                &false
-            }
-        })
-        .collect();
-
-    // game_of_life.simulation = vec![
-    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
-    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
-    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
-    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
-    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
-    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
-    //     &false, &false, &false, &true, &false, &false, &false, &false, &false, &false, &false,
-    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
-    //     &false, &false, &true, &false, &false, &false, &false, &false, &false, &false, &false,
-    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &true, &true,
-    //     &true, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
-    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
-    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
-    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
-    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
-    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
-    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
-    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
-    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
-    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
-    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
-    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
-    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
-    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
-    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
-    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
-    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
-    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
-    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
-    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
-    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
-    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
-    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
-    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
-    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
-    //     &false, &false, &false, &false, &false, &false, &false, &false, &false, &false, &false,
-    //     &false, &false, &false, &false,
-    // ];
+    // Read from the working directory and rebuilt when edited, the built in copies are used
+    // when the files aren't found
+    let mut board_shader = shaders::WatchedProgram::new(
+        "shaders/board.vert",
+        "shaders/board.frag",
+        include_str!("../shaders/board.vert"),
+        include_str!("../shaders/board.frag"),
+    )
+    .map_err(|e| e.to_string())?;
 
     let mut game_of_life_history = Vec::new();
     game_of_life_history.push(game_of_life);
 
+    // Size of the viewport in pixels, the whole drawable area of the window
+    let mut viewport = window.drawable_size();
+
     unsafe {
-        gl::Viewport(0, 0, 800, 600);
+        gl::Viewport(0, 0, viewport.0 as i32, viewport.1 as i32);
     }
 
-    let glider_width = 5;
-    let glider_height = 5;
-    let glider = vec![
-        false, false, false, false, false, false, false, true, false, false, false, false, false,
-        true, false, false, true, true, true, false, false, false, false, false, false,
-    ];
+    let mut playback = playback::Playback::new(60.0);
+    // Digits typed so far for the "run to generation" command
+    let mut generation_input = String::new();
 
-    let glider2 = vec![
-        false, false, false, false, false, false, true, false, false, false, false, false, true,
-        true, false, false, true, true, false, false, false, false, false, false, false,
-    ];
+    let board_size = (
+        game_of_life_history[0].width,
+        game_of_life_history[0].height,
+    );
+    let board_model = glm::scaling(&glm::vec3(board_size.0 as f32, board_size.1 as f32, 1.0));
 
-    let mut patterns = Vec::new();
+    let mut camera = camera::Camera::new(glm::vec2(0.0, 0.0), 1.0);
+    camera.fit(
+        glm::vec2(0.0, 0.0),
+        glm::vec2(board_size.0 as f32, board_size.1 as f32),
+        viewport,
+    );
 
-    let mut g = glider;
+    // Last known mouse position, as wheel events don't carry it
+    let mut mouse_position = (0, 0);
 
-    for _ in 0..4 {
-        let mut glider_pattern = 0;
-        for val in &g {
-            glider_pattern <<= 1;
-            glider_pattern |= if *val { 1 } else { 0 };
-        }
+    let mut resize_mode = camera::ResizeMode::Letterbox;
 
-        patterns.push(glider_pattern);
+    // Runs the simulation on the GPU instead of `GameOfLife::simulate` when set
+    let mut gpu_simulation: Option<gpu::GpuSimulation> = None;
 
-        let temp = g.clone();
+    let mut edit_mode = EditMode::Draw;
+    // Stroke in progress, the last cell it touched and the changes it made so far
+    let mut current_stroke: Option<(Stroke, (u32, u32), undo::Edit)> = None;
 
-        for j in 0..glider_height {
-            for i in 0..glider_width {
-                g[index(i, j, glider_width)] = temp[index(glider_width - 1 - j, i, glider_width)]
-            }
-        }
-    }
+    let mut undo_history = undo::UndoHistory::new();
 
-    let mut g = glider2;
+    let mut selection: Option<selection::Selection> = None;
+    // Whether the mouse is dragging the corner of the selection
+    let mut selecting = false;
+    // Pattern waiting to be pasted, drawn with its top left corner under the mouse
+    let mut paste_preview: Option<pattern::Pattern> = None;
 
-    for _ in 0..4 {
-        let mut glider_pattern = 0;
-        for val in &g {
-            glider_pattern <<= 1;
-            glider_pattern |= if *val { 1 } else { 0 };
-        }
+    let mut board_view = render::BoardView::new(board_size.0, board_size.1);
+    let mut activity = render::Activity::new(board_size.0, board_size.1);
+    activity.observe(&game_of_life_history[0]);
 
-        patterns.push(glider_pattern);
+    let mut classifier = objects::Classifier::new();
+    let mut statistics = options.statistics()?;
+    record_statistics(&mut statistics, &mut classifier, &game_of_life_history[0]);
+    // Objects of the board shown
+    let mut objects = Vec::new();
 
-        let temp = g.clone();
+    let palettes = render::Palette::all();
+    let mut palette_index = 0;
 
-        for j in 0..glider_height {
-            for i in 0..glider_width {
-                g[index(i, j, glider_width)] = temp[index(glider_width - 1 - j, i, glider_width)]
-            }
-        }
+    if let Some(palette) = &options.palette {
+        palette_index = palettes
+            .iter()
+            .position(|known| known.name == palette.name)
+            .unwrap_or(0);
+        board_view.palette = palette.clone();
     }
+    board_view.grid.visible = options.grid;
+    if let Some(major) = options.grid_major {
+        board_view.grid.major = major;
+    }
+    if let Some(overlay) = options.overlay {
+        board_view.set_overlay(overlay);
+    }
+    let trail = board_view.trail();
+    board_view.set_trail(render::Trail {
+        length: options.trail.unwrap_or(trail.length),
+        color: options.trail_color.or(trail.color),
+        ..trail
+    });
 
-    let mut play = true;
+    let mut screenshot = None;
+    let mut recording: Option<Recording> = None;
 
-    // Buffers
+    let frame_duration = Duration::from_secs_f64(1.0 / 60.0);
+    // Time a frame may spend computing generations before rendering
+    let simulation_budget = Duration::from_millis(12);
 
-    let mut simulation_rgb: Vec<u8> =
-        vec![255; (game_of_life_history[0].width * game_of_life_history[0].height * 3) as usize];
+    let mut last_frame = Instant::now();
 
-    let simulation_rgb_ptr = simulation_rgb.as_ptr();
-    let texture = Texture::new(simulation_rgb_ptr, game_of_life_history[0].width, game_of_life_history[0].height);
+    let mut hud = hud::Hud::new().map_err(|e| e.to_string())?;
+
+    // Frames rendered since `fps_start`, to update `fps` about once a second
+    let mut frame_count = 0;
+    let mut fps_start = Instant::now();
+    let mut fps = 0.0;
 
     'running: loop {
+        let frame_start = Instant::now();
+        let elapsed = frame_start - last_frame;
+        last_frame = frame_start;
+
         unsafe {
             gl::ClearColor(0.0, 0.0, 0.0, 1.0);
             gl::Clear(gl::COLOR_BUFFER_BIT);
@@ -422,6 +809,154 @@ pub fn main() -> Result<(), String> {
 
         for event in event_pump.poll_iter() {
             match event {
+                Event::KeyDown {
+                    keycode: Some(Keycode::Escape),
+                    ..
+                } if paste_preview.is_some() => paste_preview = None,
+                Event::KeyDown {
+                    keycode: Some(Keycode::Escape),
+                    ..
+                } if selection.is_some() => selection = None,
+                Event::KeyDown {
+                    keycode: Some(keycode @ Keycode::C),
+                    keymod,
+                    ..
+                }
+                | Event::KeyDown {
+                    keycode: Some(keycode @ Keycode::X),
+                    keymod,
+                    ..
+                } if ctrl(keymod) => {
+                    if let Some(bounds) = selection.map(|selection| selection.bounds()) {
+                        let board = &mut game_of_life_history[0];
+
+                        let copied = clipboard
+                            .set_clipboard_text(&pattern::rle::encode(&board.copy_region(bounds)));
+
+                        match copied {
+                            // The cells are only cut once they are in the clipboard
+                            Err(e) => eprintln!("Can't copy to the clipboard: {}", e),
+                            Ok(()) if keycode == Keycode::X => {
+                                let mut edit = undo::Edit::new("Cut");
+                                edit.extend(board.clear_region(bounds));
+                                undo_history.record(edit);
+                            }
+                            Ok(()) => {}
+                        }
+                    }
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::Z),
+                    keymod,
+                    ..
+                } if ctrl(keymod) && !shift(keymod) && current_stroke.is_none() => {
+                    undo_history.undo(&mut game_of_life_history[0])
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::Z),
+                    keymod,
+                    ..
+                }
+                | Event::KeyDown {
+                    keycode: Some(Keycode::Y),
+                    keymod,
+                    ..
+                } if ctrl(keymod) && current_stroke.is_none() => {
+                    undo_history.redo(&mut game_of_life_history[0])
+                }
+                // Behind two modifiers, as it replaces the whole board
+                Event::KeyDown {
+                    keycode: Some(Keycode::S),
+                    keymod,
+                    ..
+                } if ctrl(keymod) && shift(keymod) => {
+                    let mut edit = undo::Edit::new("Reseed");
+                    edit.extend(game_of_life_history[0].reseed(&mut rng, 0.3));
+                    undo_history.record(edit);
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::V),
+                    keymod,
+                    ..
+                } if ctrl(keymod) => {
+                    let pasted = clipboard
+                        .clipboard_text()
+                        .and_then(|text| pattern::rle::decode(&text, board_size));
+
+                    match pasted {
+                        Ok(pattern) => paste_preview = Some(pattern),
+                        Err(e) => eprintln!("Can't paste the clipboard: {}", e),
+                    }
+                }
+                Event::DropFile { filename, .. } => {
+                    match pattern::bitmap::import(
+                        filename.as_ref(),
+                        board_size,
+                        options.conversion,
+                        options.invert,
+                    ) {
+                        Ok(pattern) => paste_preview = Some(pattern),
+                        Err(e) => eprintln!("Can't import {}: {}", filename, e),
+                    }
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::Delete),
+                    ..
+                } => {
+                    if let Some(selection) = selection {
+                        let mut edit = undo::Edit::new("Clear");
+                        edit.extend(game_of_life_history[0].clear_region(selection.bounds()));
+                        undo_history.record(edit);
+                    }
+                }
+                Event::KeyDown {
+                    keycode: Some(keycode @ Keycode::R),
+                    keymod,
+                    ..
+                }
+                | Event::KeyDown {
+                    keycode: Some(keycode @ Keycode::M),
+                    keymod,
+                    ..
+                } => {
+                    let transform = match (keycode, shift(keymod)) {
+                        (Keycode::R, false) => Transform::RotateClockwise,
+                        (Keycode::R, true) => Transform::RotateCounterClockwise,
+                        (_, false) => Transform::FlipHorizontal,
+                        (_, true) => Transform::FlipVertical,
+                    };
+
+                    if let Some(pattern) = &paste_preview {
+                        paste_preview = Some(transform.apply(pattern));
+                    } else if let Some((min_x, min_y, max_x, max_y)) =
+                        selection.map(|selection| selection.bounds())
+                    {
+                        // Transform in place, keeping the top left corner of the selection
+                        let board = &mut game_of_life_history[0];
+                        let pattern =
+                            transform.apply(&board.copy_region((min_x, min_y, max_x, max_y)));
+
+                        // Pasting would wrap around the edges and overwrite cells on the other
+                        // side of the board
+                        if min_x + pattern.width > board.width
+                            || min_y + pattern.height > board.height
+                        {
+                            eprintln!(
+                                "Can't rotate the selection, it would go past the edge of the board"
+                            );
+                        } else {
+                            let mut edit = undo::Edit::new(transform.name());
+                            edit.extend(board.clear_region((min_x, min_y, max_x, max_y)));
+                            edit.extend(board.paste(&pattern, (min_x, min_y)));
+                            undo_history.record(edit);
+
+                            selection = Some(selection::Selection {
+                                anchor: (min_x, min_y),
+                                corner: (min_x + pattern.width - 1, min_y + pattern.height - 1),
+                            });
+                        }
+                    }
+                }
                 Event::Quit { .. }
                 | Event::KeyDown {
                     keycode: Some(Keycode::Escape),
@@ -430,137 +965,682 @@ pub fn main() -> Result<(), String> {
                 Event::KeyDown {
                     keycode: Some(Keycode::Space),
                     ..
-                } => play = !play,
+                } => playback.toggle(),
+                Event::KeyDown {
+                    keycode: Some(Keycode::N),
+                    ..
+                } => playback.step(),
+                Event::KeyDown {
+                    keycode: Some(Keycode::Equals),
+                    ..
+                }
+                | Event::KeyDown {
+                    keycode: Some(Keycode::KpPlus),
+                    ..
+                } => playback.faster(),
+                Event::KeyDown {
+                    keycode: Some(Keycode::Minus),
+                    ..
+                }
+                | Event::KeyDown {
+                    keycode: Some(Keycode::KpMinus),
+                    ..
+                } => playback.slower(),
+                Event::TextInput { text, .. } => {
+                    generation_input.extend(text.chars().filter(char::is_ascii_digit))
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::Backspace),
+                    ..
+                } => {
+                    generation_input.pop();
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::H),
+                    ..
+                } => hud.toggle(),
+                Event::KeyDown {
+                    keycode: Some(Keycode::F12),
+                    keymod,
+                    ..
+                } => {
+                    screenshot = Some(if shift(keymod) {
+                        Screenshot::View
+                    } else {
+                        Screenshot::Board
+                    })
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::F9),
+                    ..
+                } => {
+                    let board = &game_of_life_history[0];
+                    let path = format!("generation-{}.svg", board.generation);
+                    let text = export::svg::export(
+                        &board_view.style(),
+                        board,
+                        &activity,
+                        &objects,
+                        options.crop.region(board, 0),
+                        &options.svg_options(),
+                    );
+
+                    match std::fs::write(&path, text) {
+                        Ok(()) => report(&options, &format!("Saved {}", path)),
+                        Err(e) => eprintln!("Can't save {}: {}", path, e),
+                    }
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::F10),
+                    ..
+                } => {
+                    recording = match recording.take() {
+                        Some(recording) => {
+                            report(&options, &format!("Recorded {} frames", recording.frames));
+                            None
+                        }
+                        None => {
+                            let board = &game_of_life_history[0];
+                            let path = format!("generation-{}.gif", board.generation);
+
+                            match export::gif::GifRecorder::create(path.as_ref(), options.delay) {
+                                Ok(recorder) => {
+                                    report(&options, &format!("Recording {}", path));
+                                    Some(Recording {
+                                        recorder,
+                                        // Only the current bounding box of the pattern is known
+                                        region: options.crop.region(board, 0),
+                                        next_generation: board.generation,
+                                        frames: 0,
+                                    })
+                                }
+                                Err(e) => {
+                                    eprintln!("Can't record a GIF: {}", e);
+                                    None
+                                }
+                            }
+                        }
+                    };
+                    // So the current board is the first frame
+                    board_view.refresh();
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::O),
+                    ..
+                } => board_view.set_overlay(board_view.overlay().next()),
+                Event::KeyDown {
+                    keycode: Some(Keycode::P),
+                    ..
+                } => {
+                    palette_index = (palette_index + 1) % palettes.len();
+                    board_view.palette = palettes[palette_index].clone();
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::D),
+                    ..
+                } => {
+                    let trail = board_view.trail();
+                    board_view.set_trail(render::Trail {
+                        decay: trail.decay.next(),
+                        ..trail
+                    });
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::Tab),
+                    ..
+                } => board_view.set_coloring(board_view.coloring().next()),
+                Event::KeyDown {
+                    keycode: Some(keycode @ Keycode::LeftBracket),
+                    ..
+                }
+                | Event::KeyDown {
+                    keycode: Some(keycode @ Keycode::RightBracket),
+                    ..
+                } => {
+                    // Halves or doubles the trail length, the age range or the heat window
+                    let scale = |range: u64| match keycode {
+                        Keycode::LeftBracket => range / 2,
+                        _ => (range * 2).max(1),
+                    };
+
+                    match board_view.coloring() {
+                        render::Coloring::Trail => {
+                            let trail = board_view.trail();
+                            board_view.set_trail(render::Trail {
+                                length: scale(trail.length),
+                                ..trail
+                            });
+                        }
+                        render::Coloring::Age => {
+                            board_view.set_age_range(scale(board_view.age_range()))
+                        }
+                        render::Coloring::Heat => {
+                            activity.set_heat_window(scale(activity.heat_window()));
+                            board_view.refresh();
+                        }
+                    }
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::G),
+                    keymod,
+                    ..
+                } if ctrl(keymod) => board_view.grid.visible = !board_view.grid.visible,
+                // Alt + Return toggles fullscreen
+                Event::KeyDown {
+                    keycode: Some(Keycode::G),
+                    keymod,
+                    ..
+                }
+                | Event::KeyDown {
+                    keycode: Some(Keycode::Return),
+                    keymod,
+                    ..
+                } if !alt(keymod) => {
+                    if let Ok(generation) = generation_input.parse() {
+                        playback.run_to(generation);
+                    }
+                    generation_input.clear();
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::B),
+                    ..
+                } => {
+                    gpu_simulation = match gpu_simulation {
+                        Some(_) => None,
+                        None => match gpu::GpuSimulation::new(board_size.0, board_size.1) {
+                            Ok(simulation) => Some(simulation),
+                            Err(error) => {
+                                eprintln!("Cannot simulate on the GPU: {}", error);
+                                None
+                            }
+                        },
+                    }
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::T),
+                    ..
+                } => {
+                    edit_mode = match edit_mode {
+                        EditMode::Draw => EditMode::Toggle,
+                        EditMode::Toggle => EditMode::Draw,
+                    }
+                }
+                Event::MouseButtonDown {
+                    mouse_btn, x, y, ..
+                } if paste_preview.is_some() => {
+                    let cell = window_to_cell(x, y, &camera, &window, board_size);
+
+                    if let (MouseButton::Left, Some(cell), Some(pattern)) =
+                        (mouse_btn, cell, &paste_preview)
+                    {
+                        let mut edit = undo::Edit::new("Paste");
+                        edit.extend(game_of_life_history[0].paste(pattern, cell));
+                        undo_history.record(edit);
+                    }
+
+                    // Any click ends the paste, right click without pasting
+                    if mouse_btn == MouseButton::Left || mouse_btn == MouseButton::Right {
+                        paste_preview = None;
+                    }
+                }
+                Event::MouseButtonDown {
+                    mouse_btn: MouseButton::Left,
+                    x,
+                    y,
+                    ..
+                } if shift(sdl_context.keyboard().mod_state()) => {
+                    selection = window_to_cell(x, y, &camera, &window, board_size)
+                        .map(selection::Selection::new);
+                    selecting = selection.is_some();
+                }
+                Event::MouseButtonDown {
+                    mouse_btn, x, y, ..
+                } => {
+                    let stroke = match (mouse_btn, edit_mode) {
+                        (MouseButton::Left, EditMode::Draw) => Some(Stroke::Set),
+                        (MouseButton::Left, EditMode::Toggle) => Some(Stroke::Toggle),
+                        (MouseButton::Right, _) => Some(Stroke::Clear),
+                        _ => None,
+                    };
+
+                    let board = &mut game_of_life_history[0];
+                    let cell = window_to_cell(x, y, &camera, &window, board_size);
+
+                    if let (Some(stroke), Some((cell_x, cell_y))) = (stroke, cell) {
+                        let mut edit = undo::Edit::new(stroke.name());
+                        edit.extend(board.edit(stroke, cell_x, cell_y));
+                        current_stroke = Some((stroke, (cell_x, cell_y), edit));
+                    }
+                }
+                Event::MouseMotion {
+                    mousestate,
+                    x,
+                    y,
+                    xrel,
+                    yrel,
+                    ..
+                } => {
+                    mouse_position = (x, y);
+
+                    if mousestate.middle() {
+                        let (dx, dy) = window_to_pixels(&window, xrel as f32, yrel as f32);
+                        camera.pan(dx, dy);
+                    }
+
+                    if let (true, Some(selection)) = (selecting, &mut selection) {
+                        if let Some(cell) = window_to_cell(x, y, &camera, &window, board_size) {
+                            selection.corner = cell;
+                        }
+                    }
+
+                    if let Some((stroke, last_cell, edit)) = &mut current_stroke {
+                        let board = &mut game_of_life_history[0];
+                        let cell = window_to_cell(x, y, &camera, &window, board_size);
+
+                        if let Some(cell) = cell.filter(|cell| cell != last_cell) {
+                            for (cell_x, cell_y) in line_cells(*last_cell, cell).into_iter().skip(1)
+                            {
+                                edit.extend(board.edit(*stroke, cell_x, cell_y));
+                            }
+                            *last_cell = cell;
+                        }
+                    }
+                }
+                Event::MouseButtonUp { .. } => {
+                    if let Some((_, _, edit)) = current_stroke.take() {
+                        undo_history.record(edit);
+                    }
+                    selecting = false;
+                }
+                Event::MouseWheel { y: scroll, .. } => {
+                    let (x, y) =
+                        window_to_pixels(&window, mouse_position.0 as f32, mouse_position.1 as f32);
+                    camera.zoom_at(1.25f32.powi(scroll), x, y, viewport);
+                }
+                Event::Window {
+                    win_event: WindowEvent::SizeChanged(..),
+                    ..
+                } => {
+                    let new_viewport = window.drawable_size();
+
+                    unsafe {
+                        gl::Viewport(0, 0, new_viewport.0 as i32, new_viewport.1 as i32);
+                    }
+
+                    camera.resize(viewport, new_viewport, resize_mode);
+                    viewport = new_viewport;
+                }
+                Event::KeyDown {
+                    keycode: Some(keycode @ Keycode::F11),
+                    keymod,
+                    ..
+                }
+                | Event::KeyDown {
+                    keycode: Some(keycode @ Keycode::Return),
+                    keymod,
+                    ..
+                } if keycode == Keycode::F11 || alt(keymod) => {
+                    let fullscreen = match window.fullscreen_state() {
+                        FullscreenType::Off => FullscreenType::Desktop,
+                        _ => FullscreenType::Off,
+                    };
+                    if let Err(e) = window.set_fullscreen(fullscreen) {
+                        eprintln!("Can't switch the window to full screen: {}", e);
+                    }
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::L),
+                    ..
+                } => {
+                    resize_mode = match resize_mode {
+                        camera::ResizeMode::Letterbox => camera::ResizeMode::Expand,
+                        camera::ResizeMode::Expand => camera::ResizeMode::Letterbox,
+                    }
+                }
+                Event::KeyDown {
+                    keycode: Some(keycode @ Keycode::Left),
+                    ..
+                }
+                | Event::KeyDown {
+                    keycode: Some(keycode @ Keycode::Right),
+                    ..
+                }
+                | Event::KeyDown {
+                    keycode: Some(keycode @ Keycode::Up),
+                    ..
+                }
+                | Event::KeyDown {
+                    keycode: Some(keycode @ Keycode::Down),
+                    ..
+                } => {
+                    let (dx, dy) = match keycode {
+                        Keycode::Left => (32.0, 0.0),
+                        Keycode::Right => (-32.0, 0.0),
+                        Keycode::Up => (0.0, 32.0),
+                        _ => (0.0, -32.0),
+                    };
+                    camera.pan(dx, dy);
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::F),
+                    ..
+                } => {
+                    if let Some((min_x, min_y, max_x, max_y)) =
+                        game_of_life_history[0].population_bounds()
+                    {
+                        camera.fit(
+                            glm::vec2(min_x as f32, min_y as f32),
+                            glm::vec2((max_x + 1) as f32, (max_y + 1) as f32),
+                            viewport,
+                        );
+                    }
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::C),
+                    ..
+                } => {
+                    if let Some((x, y)) = game_of_life_history[0].population_center() {
+                        camera.center = glm::vec2(x + 0.5, y + 0.5);
+                    }
+                }
                 _ => {}
             }
         }
 
         // UPDATE
-        if play {
-            let new_game_of_life = game_of_life_history[0].simulate();
-            game_of_life_history.insert(0, new_game_of_life);
+        let steps = playback.steps(elapsed, game_of_life_history[0].generation);
 
-            if game_of_life_history.len() > 10 {
-                game_of_life_history.pop();
-            }
-        }
+        // Edits made since the last step
+        activity.observe(&game_of_life_history[0]);
 
-        let mut glider_board = vec![
-            255;
-            (game_of_life_history[0].width * game_of_life_history[0].height * 3)
-                as usize
-        ];
-
-        let width = game_of_life_history[0].width;
-        let height = game_of_life_history[0].height;
-
-        let mut glider_indices = Vec::new();
-
-        for x in 0..width {
-            for y in 0..height {
-                let mut pattern = 0;
-                for i in 0..5 {
-                    for j in 0..5 {
-                        let val = game_of_life_history[0].simulation
-                            [index((x + i) % width, (y + j) % height, width) as usize];
-                        pattern <<= 1;
-                        pattern |= if *val { 1 } else { 0 };
-                    }
+        if let Some(gpu) = gpu_simulation.as_mut().filter(|_| steps > 0) {
+            // Edits are made on the CPU board, which is only uploaded when it was edited since it
+            // was read back
+            gpu.upload(&game_of_life_history[0]);
+
+            for _ in 0..steps {
+                if frame_start.elapsed() > simulation_budget {
+                    playback.drop_backlog();
+                    break;
                 }
 
-                if patterns.iter().any(|&p| p == pattern) {
-                    for i in 0..5 {
-                        for j in 0..5 {
-                            let index = index((x + i) % width, (y + j) % height, width) as usize;
-                            let val = game_of_life_history[0].simulation[index];
-                            if *val {
-                                glider_indices.push(index);
-                            }
-                        }
+                gpu.step(vao);
+
+                // Statistics need every generation, so each one is read back while they are written
+                if statistics.is_some() {
+                    game_of_life_history.insert(0, gpu.read());
+                    activity.observe(&game_of_life_history[0]);
+                    record_statistics(&mut statistics, &mut classifier, &game_of_life_history[0]);
+
+                    if game_of_life_history.len() > 2 {
+                        game_of_life_history.pop();
                     }
                 }
             }
+
+            // Otherwise only the last generation of the frame is read back, and kept in the
+            // history. The activity only sees that one, so ages, heat and trails count frames
+            // rather than generations when a frame runs several
+            if gpu.generation() != game_of_life_history[0].generation {
+                game_of_life_history.insert(0, gpu.read());
+                activity.observe(&game_of_life_history[0]);
+
+                // The previous generation is kept for the births and deaths of the HUD
+                if game_of_life_history.len() > 2 {
+                    game_of_life_history.pop();
+                }
+            }
+        } else {
+            for _ in 0..steps {
+                if frame_start.elapsed() > simulation_budget {
+                    playback.drop_backlog();
+                    break;
+                }
+
+                let new_game_of_life = game_of_life_history[0].simulate();
+                game_of_life_history.insert(0, new_game_of_life);
+                activity.observe(&game_of_life_history[0]);
+                record_statistics(&mut statistics, &mut classifier, &game_of_life_history[0]);
+
+                // The previous generation is kept for the births and deaths of the HUD
+                if game_of_life_history.len() > 2 {
+                    game_of_life_history.pop();
+                }
+            }
         }
 
-        for index in glider_indices {
-            glider_board[index * 3] = 255;
-            glider_board[index * 3 + 1] = 0;
-            glider_board[index * 3 + 2] = 0;
+        // Objects are only looked for when the board changed, as are the textures of the view
+        if !board_view.shows(&game_of_life_history[0]) {
+            objects = classifier.find_objects(&game_of_life_history[0]);
+
+            activity.observe(&game_of_life_history[0]);
+
+            // Generations are recorded as they are computed, this records edits and undos
+            if let Some(current) = &mut statistics {
+                if let Err(e) = current.record(&game_of_life_history[0], &objects) {
+                    eprintln!("Can't write statistics: {}", e);
+                    statistics = None;
+                }
+            }
+
+            if let Some(current) = &mut recording {
+                let board = &game_of_life_history[0];
+
+                if board.generation >= current.next_generation {
+                    let frame = render::software::render(
+                        &board_view.style(),
+                        board,
+                        &activity,
+                        &objects,
+                        None,
+                        current.region,
+                        options.scale,
+                    );
+
+                    if let Err(e) = current.recorder.add_frame(frame) {
+                        eprintln!("Can't record a GIF: {}", e);
+                        recording = None;
+                    } else {
+                        current.next_generation = board.generation + options.steps_per_frame;
+                        current.frames += 1;
+                    }
+                }
+            }
         }
 
         // RENDERING
 
-        simulation_rgb = simulation_rgb.iter().map(|_| 255).collect();
+        let mouse_cell = window_to_cell(
+            mouse_position.0,
+            mouse_position.1,
+            &camera,
+            &window,
+            board_size,
+        );
 
-        let mut past = (game_of_life_history.len() - 1) as u8;
-        for gol in game_of_life_history
-            .iter()
-            .rev()
-            .take(game_of_life_history.len() - 1)
-        {
-            // for gol in game_of_life_history.iter().take(1) {
-            for (index, &&cell) in gol.simulation.iter().enumerate() {
-                if cell {
-                    simulation_rgb[index * 3] = past * 12 as u8;
-                    simulation_rgb[index * 3 + 1] = past * 12 as u8;
-                    simulation_rgb[index * 3 + 2] = past * 12 as u8;
+        board_view.set_paste_preview(paste_preview.as_ref().zip(mouse_cell));
+
+        board_shader.reload_if_changed();
+
+        let mut renderer = render::GlRenderer {
+            view: &mut board_view,
+            program: &board_shader.program,
+            transform: camera.view_projection(viewport) * board_model,
+            vao,
+            viewport,
+        };
+        renderer.render(&render::Scene {
+            board: &game_of_life_history[0],
+            activity: &activity,
+            objects: &objects,
+            selection: selection.map(|selection| selection.bounds()),
+        });
+
+        // Viewport pixels per window point, such as 1.5 on some HiDPI displays
+        let pixel_ratio = match window.size().0 {
+            0 => 1.0,
+            width => (viewport.0 as f32 / width as f32).max(1.0),
+        };
+
+        // Labels are only readable with cells of a few pixels, and only the first ones are drawn
+        // so crowded boards stay fast
+        if board_view.overlay() == render::Overlay::Labels && camera.zoom >= 4.0 {
+            let mut labels = Vec::new();
+
+            for object in objects.iter().take(256) {
+                // Under the bounding box
+                let (x, y) = object.origin;
+                let position = camera.world_to_window(
+                    glm::vec2(x as f32, (y + object.size.1 + 1) as f32),
+                    viewport,
+                );
+
+                if position.x >= 0.0
+                    && position.y >= 0.0
+                    && position.x < viewport.0 as f32
+                    && position.y < viewport.1 as f32
+                {
+                    labels.push((object.class.label(), (position.x as u32, position.y as u32)));
                 }
             }
-            past -= 1;
+
+            hud.draw_labels(&labels, vao, viewport, pixel_ratio);
         }
 
-        let gol = &game_of_life_history[0];
+        frame_count += 1;
+        if fps_start.elapsed() >= Duration::from_secs(1) {
+            fps = frame_count as f64 / fps_start.elapsed().as_secs_f64();
+            frame_count = 0;
+            fps_start = Instant::now();
+        }
 
-        // for gol in game_of_life_history.iter().take(1) {
-        for (index, &&cell) in gol.simulation.iter().enumerate() {
-            if cell {
-                simulation_rgb[index * 3] = 0 as u8;
-                simulation_rgb[index * 3 + 1] = 0 as u8;
-                simulation_rgb[index * 3 + 2] = 0 as u8;
+        if hud.visible {
+            let gol = &game_of_life_history[0];
+
+            let (births, deaths) = match game_of_life_history.get(1) {
+                Some(previous) => gol.changes_since(previous),
+                None => (0, 0),
+            };
+
+            let speed = if let Some(target) = playback.target_generation {
+                format!("Running to generation {}", target)
+            } else if playback.playing {
+                format!("Speed: {} steps/s", playback.steps_per_second)
+            } else {
+                format!("Speed: {} steps/s (paused)", playback.steps_per_second)
+            };
+
+            let mut lines = vec![
+                format!("Generation: {}", gol.generation),
+                match mouse_cell {
+                    Some((x, y)) => format!("Cell: {}, {}", x, y),
+                    None => "Cell: -".to_string(),
+                },
+                format!("Population: {}", gol.population()),
+                format!("Births: {}  Deaths: {}", births, deaths),
+                format!("Rule: B3/S23  Topology: {}x{} torus", gol.width, gol.height),
+                format!(
+                    "Colours: {}  {}{}",
+                    board_view.palette.name,
+                    board_view.coloring().name(),
+                    match board_view.coloring() {
+                        render::Coloring::Trail => format!(
+                            " of {} {}",
+                            board_view.trail().length,
+                            board_view.trail().decay.name()
+                        ),
+                        render::Coloring::Age => format!(" up to {}", board_view.age_range()),
+                        render::Coloring::Heat => format!(" over {}", activity.heat_window()),
+                    }
+                ),
+                speed,
+                format!(
+                    "FPS: {:.0}  Simulation: {}",
+                    fps,
+                    if gpu_simulation.is_some() {
+                        "GPU"
+                    } else {
+                        "CPU"
+                    }
+                ),
+                format!(
+                    "Undo: {}  Redo: {}",
+                    undo_history.next_undo().unwrap_or("-"),
+                    undo_history.next_redo().unwrap_or("-")
+                ),
+            ];
+
+            if let Some(recording) = &recording {
+                lines.push(format!("Recording GIF: {} frames", recording.frames));
             }
-        }
 
-        // Creates a checkerboard!
-
-        // for i in 0..game_of_life_history[0].width {
-        //     for j in 0..game_of_life_history[0].height {
-        //         let x = i * 3;
-        //         let y = j * 3;
-        //         let index = index(x, y, game_of_life_history[0].width) as usize;
-        //         if i % 2 == 1 - (j % 2) {
-        //             simulation_rgb[index] = 0;
-        //             simulation_rgb[index + 1] = 0;
-        //             simulation_rgb[index + 2] = 0;
-        //         } else {
-        //             simulation_rgb[index] = 255;
-        //             simulation_rgb[index + 1] = 255;
-        //             simulation_rgb[index + 2] = 255;
-        //         }
-        //     }
-        // }
-
-        simulation_rgb = simulation_rgb
-            .iter()
-            .zip(glider_board)
-            .map(|(&val, col)| if val == 0 { col } else { val })
-            .collect();
+            if !generation_input.is_empty() {
+                lines.push(format!("Run to generation: {}_", generation_input));
+            }
 
-        let simulation_rgb_ptr = simulation_rgb.as_ptr();
-        texture.update(simulation_rgb_ptr);
+            if let Some(error) = &board_shader.error {
+                lines.extend(error.to_string().lines().take(4).map(str::to_string));
+            }
 
-        unsafe {
-            gl::ActiveTexture(gl::TEXTURE0);
-            gl::BindTexture(gl::TEXTURE_2D, texture.id);
+            let mut legend = Vec::new();
 
-            // gl::PolygonMode(gl::FRONT_AND_BACK, gl::LINE);
+            if board_view.overlay() != render::Overlay::Hidden {
+                let mut counts = [0; 4];
+                for object in &objects {
+                    counts[object.class.index()] += 1;
+                }
+
+                for (index, name) in objects::CLASS_NAMES.iter().enumerate() {
+                    let colour = board_view.palette.objects[index] * 255.0;
+                    legend.push((
+                        [colour.x as u8, colour.y as u8, colour.z as u8],
+                        format!("{}: {}", name, counts[index]),
+                    ));
+                }
+            }
 
-            gl::UseProgram(shader_program.id);
-            gl::BindVertexArray(vao);
-            gl::DrawElements(gl::TRIANGLES, 6, gl::UNSIGNED_INT, std::ptr::null_mut());
-            // gl::DrawArrays(gl::POINTS, 0, 4);
+            hud.draw(&lines, &legend, vao, viewport, pixel_ratio);
         }
+
+        if let Some(kind) = screenshot.take() {
+            let board = &game_of_life_history[0];
+
+            let (path, image) = match kind {
+                Screenshot::Board => {
+                    let mut renderer = render::SoftwareRenderer::new(
+                        board_view.style(),
+                        options.crop.region(board, 0),
+                        options.scale,
+                    );
+                    renderer.render(&render::Scene {
+                        board,
+                        activity: &activity,
+                        objects: &objects,
+                        selection: None,
+                    });
+
+                    (
+                        format!("generation-{}.png", board.generation),
+                        renderer.read_pixels(),
+                    )
+                }
+                Screenshot::View => (
+                    format!("generation-{}-view.png", board.generation),
+                    render::read_viewport(viewport),
+                ),
+            };
+
+            match image.save(&path) {
+                Ok(()) => report(&options, &format!("Saved {}", path)),
+                Err(e) => eprintln!("Can't save {}: {}", path, e),
+            }
+        }
+
         window.gl_swap_window();
-        ::std::thread::sleep(::std::time::Duration::new(0, 1_000_000_000u32 / 60));
+
+        if let Some(remaining) = frame_duration.checked_sub(frame_start.elapsed()) {
+            ::std::thread::sleep(remaining);
+        }
     }
 
     Ok(())
@@ -0,0 +1,293 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::GameOfLife;
+
+// Live cells at most this far apart, in cells on each axis, are part of the same object, as
+// they would interact with each other
+const GROUPING_DISTANCE: i32 = 2;
+// Longest period looked for when classifying an object
+const MAX_PERIOD: u32 = 32;
+// Objects with more cells are classified as active without simulating them
+const MAX_CELLS: usize = 400;
+// Number of shapes whose class is remembered
+const CACHE_SIZE: usize = 4096;
+
+// Names of the classes, in the order of `Class::index`
+pub const CLASS_NAMES: [&str; 4] = ["Still lifes", "Oscillators", "Spaceships", "Active"];
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Class {
+    StillLife,
+    Oscillator { period: u32 },
+    // Moves by (dx, dy) cells every period
+    Spaceship { period: u32, dx: i32, dy: i32 },
+    // Anything else: growing, dying, or with a longer period than looked for
+    Active,
+}
+
+impl Class {
+    // Position of the class in CLASS_NAMES and in the object colours of palettes
+    pub fn index(self) -> usize {
+        match self {
+            Class::StillLife => 0,
+            Class::Oscillator { .. } => 1,
+            Class::Spaceship { .. } => 2,
+            Class::Active => 3,
+        }
+    }
+
+    // Short description drawn next to an object, such as "P2 oscillator" or "c/4 spaceship"
+    pub fn label(self) -> String {
+        match self {
+            Class::StillLife => "Still life".to_string(),
+            Class::Oscillator { period } => format!("P{} oscillator", period),
+            Class::Spaceship { period, dx, dy } => {
+                // Written as a reduced fraction of c, one cell per generation
+                let distance = dx.abs().max(dy.abs()) as u32;
+                let divisor = gcd(distance, period);
+                let (distance, period) = (distance / divisor, period / divisor);
+
+                let speed = if distance == 1 {
+                    format!("c/{}", period)
+                } else {
+                    format!("{}c/{}", distance, period)
+                };
+                format!("{} spaceship", speed)
+            }
+            Class::Active => "Active".to_string(),
+        }
+    }
+}
+
+pub struct Object {
+    // Cells of the object on the board
+    pub cells: Vec<(u32, u32)>,
+    // Top left cell of the bounding box, and its width and height. The box can wrap around the
+    // edges of the board
+    pub origin: (u32, u32),
+    pub size: (u32, u32),
+    pub class: Class,
+}
+
+// Splits boards into objects and classifies them, remembering the class of the shapes it saw
+pub struct Classifier {
+    cache: HashMap<Vec<(i32, i32)>, Class>,
+}
+
+impl Classifier {
+    pub fn new() -> Self {
+        Classifier {
+            cache: HashMap::new(),
+        }
+    }
+
+    pub fn find_objects(&mut self, board: &GameOfLife) -> Vec<Object> {
+        let mut objects = Vec::new();
+        let mut visited = vec![false; board.simulation.len()];
+
+        for start in 0..board.simulation.len() {
+            if visited[start] || !*board.simulation[start] {
+                continue;
+            }
+            visited[start] = true;
+
+            // Cells of the object, with coordinates that don't wrap around the edges
+            let start = (
+                (start as u32 % board.width) as i32,
+                (start as u32 / board.width) as i32,
+            );
+            let mut cells = vec![start];
+            let mut next = 0;
+
+            while next < cells.len() {
+                let (x, y) = cells[next];
+                next += 1;
+
+                for dy in -GROUPING_DISTANCE..=GROUPING_DISTANCE {
+                    for dx in -GROUPING_DISTANCE..=GROUPING_DISTANCE {
+                        let (cell_x, cell_y) = wrap(board, x + dx, y + dy);
+                        let index = crate::index(cell_x, cell_y, board.width) as usize;
+
+                        if !visited[index] && *board.simulation[index] {
+                            visited[index] = true;
+                            cells.push((x + dx, y + dy));
+                        }
+                    }
+                }
+            }
+
+            let (shape, (min_x, min_y)) = normalize(&cells);
+            let (max_x, max_y) = shape.iter().fold((0, 0), |(max_x, max_y), &(x, y)| {
+                (max_x.max(x), max_y.max(y))
+            });
+
+            objects.push(Object {
+                cells: cells.iter().map(|&(x, y)| wrap(board, x, y)).collect(),
+                origin: wrap(board, min_x, min_y),
+                size: (max_x as u32 + 1, max_y as u32 + 1),
+                class: self.classify(shape),
+            });
+        }
+
+        objects
+    }
+
+    fn classify(&mut self, shape: Vec<(i32, i32)>) -> Class {
+        if let Some(&class) = self.cache.get(&shape) {
+            return class;
+        }
+
+        let class = if shape.len() > MAX_CELLS {
+            Class::Active
+        } else {
+            classify(&shape)
+        };
+
+        if self.cache.len() >= CACHE_SIZE {
+            self.cache.clear();
+        }
+        self.cache.insert(shape, class);
+
+        class
+    }
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+fn wrap(board: &GameOfLife, x: i32, y: i32) -> (u32, u32) {
+    (
+        x.rem_euclid(board.width as i32) as u32,
+        y.rem_euclid(board.height as i32) as u32,
+    )
+}
+
+// Moves cells so that their bounding box starts at (0, 0), and sorts them. Returns them with the
+// former top left corner of the box
+fn normalize(cells: &[(i32, i32)]) -> (Vec<(i32, i32)>, (i32, i32)) {
+    let min_x = cells.iter().map(|&(x, _)| x).min().unwrap_or(0);
+    let min_y = cells.iter().map(|&(_, y)| y).min().unwrap_or(0);
+
+    let mut shape: Vec<(i32, i32)> = cells.iter().map(|&(x, y)| (x - min_x, y - min_y)).collect();
+    shape.sort_unstable();
+
+    (shape, (min_x, min_y))
+}
+
+// Runs the shape alone on an unbounded plane until it comes back, possibly somewhere else
+fn classify(shape: &[(i32, i32)]) -> Class {
+    let mut cells: Vec<(i32, i32)> = shape.to_vec();
+
+    for period in 1..=MAX_PERIOD {
+        cells = step(&cells);
+
+        if cells.is_empty() {
+            return Class::Active;
+        }
+
+        let (next_shape, (dx, dy)) = normalize(&cells);
+
+        if next_shape == shape {
+            return match (period, dx, dy) {
+                (1, 0, 0) => Class::StillLife,
+                (_, 0, 0) => Class::Oscillator { period },
+                _ => Class::Spaceship { period, dx, dy },
+            };
+        }
+    }
+
+    Class::Active
+}
+
+// Next generation of live cells on an unbounded plane
+fn step(cells: &[(i32, i32)]) -> Vec<(i32, i32)> {
+    let alive: HashSet<(i32, i32)> = cells.iter().copied().collect();
+    let mut neighbor_counts: HashMap<(i32, i32), u32> = HashMap::new();
+
+    for &(x, y) in cells {
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                if dx != 0 || dy != 0 {
+                    *neighbor_counts.entry((x + dx, y + dy)).or_insert(0) += 1;
+                }
+            }
+        }
+    }
+
+    neighbor_counts
+        .into_iter()
+        .filter(|(cell, count)| *count == 3 || (*count == 2 && alive.contains(cell)))
+        .map(|(cell, _)| cell)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board(width: u32, height: u32, cells: &[(u32, u32)]) -> GameOfLife<'static> {
+        let mut board = GameOfLife::new(width, height);
+        for &(x, y) in cells {
+            board.set(x, y, true);
+        }
+        board
+    }
+
+    // Labels of the objects found on a board, in the order of their first cell
+    fn labels(cells: &[(u32, u32)]) -> Vec<String> {
+        Classifier::new()
+            .find_objects(&board(16, 16, cells))
+            .iter()
+            .map(|object| object.class.label())
+            .collect()
+    }
+
+    #[test]
+    fn classifies_a_block_as_a_still_life() {
+        assert_eq!(labels(&[(4, 4), (5, 4), (4, 5), (5, 5)]), ["Still life"]);
+    }
+
+    #[test]
+    fn classifies_a_blinker_as_an_oscillator() {
+        assert_eq!(labels(&[(4, 3), (4, 4), (4, 5)]), ["P2 oscillator"]);
+    }
+
+    #[test]
+    fn classifies_a_glider_as_a_spaceship() {
+        let glider = [(5, 4), (6, 5), (4, 6), (5, 6), (6, 6)];
+        let objects = Classifier::new().find_objects(&board(16, 16, &glider));
+
+        assert_eq!(objects.len(), 1);
+        assert!(matches!(
+            objects[0].class,
+            Class::Spaceship {
+                period: 4,
+                dx: 1,
+                dy: 1
+            }
+        ));
+        assert_eq!(objects[0].class.label(), "c/4 spaceship");
+    }
+
+    #[test]
+    fn finds_objects_across_the_edges() {
+        // A blinker across the left and right edges, and a block across the corners
+        let cells = [(15, 8), (0, 8), (1, 8), (15, 15), (0, 15), (15, 0), (0, 0)];
+        let objects = Classifier::new().find_objects(&board(16, 16, &cells));
+
+        assert_eq!(objects.len(), 2);
+
+        let block = &objects[0];
+        assert!(block.class == Class::StillLife);
+        assert_eq!((block.origin, block.size), ((15, 15), (2, 2)));
+
+        let blinker = &objects[1];
+        assert!(blinker.class == Class::Oscillator { period: 2 });
+        assert_eq!((blinker.origin, blinker.size), ((15, 8), (3, 1)));
+    }
+}
@@ -0,0 +1,331 @@
+use std::path::{Path, PathBuf};
+
+use crate::export::svg::SvgOptions;
+use crate::export::video::VideoFormat;
+use crate::export::Crop;
+use crate::pattern::bitmap::Conversion;
+use crate::render::{Overlay, Palette, Style};
+use crate::statistics::{Statistics, StatisticsFormat};
+use nalgebra_glm as glm;
+
+pub const USAGE: &str = "\
+Usage: game-of-life [options]
+
+Options:
+  --image FILE        Start from an image (PNG, JPEG...) scaled to the board, dark pixels
+                      becoming live cells. Images dropped on the window are pasted the same way
+  --threshold N       Pixels darker than N, from 0 to 255, become live cells (default 128)
+  --dither            Dither the shades of the image instead of using a threshold
+  --invert            Make light pixels live cells instead
+  --screenshot FILE   Save the board to a PNG file after --generations and exit, without
+                      opening a window
+  --gif FILE          Record an animated GIF of the generations after --generations and
+                      exit, without opening a window. F10 records one in the window
+  --video FILE        Write the generations after --generations as uncompressed video to a
+                      file, or to the standard output for -, and exit, without a window
+  --video-format F    raw for packed RGB bytes, or y4m (default: y4m for .y4m files)
+  --resolution WxH    Size of the video frames, the board is scaled by whole pixels per cell
+                      and centred (default: the cropped board at --scale)
+  --frame-rate N      Frames per second written in Y4M headers (default 30)
+  --svg FILE          Save the board as an SVG image after --generations and exit, without
+                      opening a window. F9 saves one in the window
+  --svg-paths         Merge the cells of each colour into a path instead of a rect per cell
+  --annotate          Write the generation, rule and board size under the SVG
+  --stats FILE        Write a record per generation (population, births, deaths, bounding box,
+                      density and objects of each class) to a file, or to the standard output
+                      for -, in the window, the terminal (to a file only) or without a window
+  --stats-format F    csv or jsonl for JSON Lines (default: jsonl for .jsonl and .json files)
+  --no-window         Run --generations and the recording without a window, for --stats alone
+  --frames N          Frames of the GIF or video (default 100)
+  --steps-per-frame N Generations between frames (default 1)
+  --delay MS          Time each GIF frame is shown, and between generations in the terminal,
+                      in milliseconds (default 100)
+  --crop REGION       Part of the board exported: board (default), pattern for the bounding
+                      box of the live cells over the recording, or X,Y,WIDTH,HEIGHT
+  --generations N     Generations to run before exporting (default 0)
+  --scale N           Pixels per cell of screenshots, GIFs and videos (default 1)
+  --palette NAME      Colour palette: classic, dark, colour-blind or colour-blind-dark
+  --terminal          Run in the terminal instead of a window, with ANSI colours. Space pauses,
+                      N steps, + and - change the speed (--delay), the arrows pan boards
+                      larger than the terminal and Q quits
+  --braille           Draw 2x4 cells per character with braille dots in the terminal, instead
+                      of 1x2 with half blocks
+  --trail N           Generations dead cells stay in the trail, 0 for none (default 10)
+  --trail-color RGB   Colour of the cells that just died, as RRGGBB in hexadecimal (default: from
+                      the palette)
+  --overlay MODE      Objects shown: hidden, classes (default), boxes or labels
+  --grid              Draw grid lines, in the window and in exports
+  --grid-major N      Cells between major grid lines (default 10)
+  --gpu-check N       Step N generations on the GPU and on the CPU, from the start board and
+                      from a small board with patterns across its edges, and exit with an
+                      error if they differ. LIBGL_ALWAYS_SOFTWARE=1 runs it without a GPU
+  --help              Show this message";
+
+// Command line options
+pub struct Options {
+    pub image: Option<PathBuf>,
+    pub conversion: Conversion,
+    pub invert: bool,
+    pub screenshot: Option<PathBuf>,
+    pub gif: Option<PathBuf>,
+    pub video: Option<PathBuf>,
+    pub video_format: Option<VideoFormat>,
+    // Size of the video frames, in pixels
+    pub resolution: Option<(u32, u32)>,
+    pub frame_rate: u32,
+    pub svg: Option<PathBuf>,
+    // Rows of cells merged into paths in SVGs
+    pub svg_paths: bool,
+    pub annotate: bool,
+    pub stats: Option<PathBuf>,
+    pub stats_format: Option<StatisticsFormat>,
+    pub no_window: bool,
+    pub frames: u64,
+    pub steps_per_frame: u64,
+    // Time each frame of a GIF is shown, in milliseconds
+    pub delay: u32,
+    pub crop: Crop,
+    pub generations: u64,
+    // Pixels per cell of screenshots, GIFs and videos
+    pub scale: u32,
+    pub palette: Option<Palette>,
+    pub trail: Option<u64>,
+    pub trail_color: Option<glm::Vec3>,
+    pub overlay: Option<Overlay>,
+    pub grid: bool,
+    // Cells between major grid lines
+    pub grid_major: Option<u32>,
+    pub terminal: bool,
+    pub braille: bool,
+    // Generations compared between the GPU and the CPU simulations
+    pub gpu_check: Option<u64>,
+    pub help: bool,
+}
+
+impl Options {
+    pub fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut options = Options {
+            image: None,
+            conversion: Conversion::Threshold(128),
+            invert: false,
+            screenshot: None,
+            gif: None,
+            video: None,
+            video_format: None,
+            resolution: None,
+            frame_rate: 30,
+            svg: None,
+            svg_paths: false,
+            annotate: false,
+            stats: None,
+            stats_format: None,
+            no_window: false,
+            frames: 100,
+            steps_per_frame: 1,
+            delay: 100,
+            crop: Crop::Board,
+            generations: 0,
+            scale: 1,
+            palette: None,
+            trail: None,
+            trail_color: None,
+            overlay: None,
+            grid: false,
+            grid_major: None,
+            terminal: false,
+            braille: false,
+            gpu_check: None,
+            help: false,
+        };
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--image" => options.image = Some(value(&arg, args.next())?.into()),
+                "--threshold" => {
+                    options.conversion = Conversion::Threshold(number(&arg, args.next())?)
+                }
+                "--dither" => options.conversion = Conversion::Dither,
+                "--invert" => options.invert = true,
+                "--screenshot" => options.screenshot = Some(value(&arg, args.next())?.into()),
+                "--gif" => options.gif = Some(value(&arg, args.next())?.into()),
+                "--video" => options.video = Some(value(&arg, args.next())?.into()),
+                "--video-format" => {
+                    options.video_format = Some(VideoFormat::parse(&value(&arg, args.next())?)?)
+                }
+                "--resolution" => options.resolution = Some(size(&value(&arg, args.next())?)?),
+                "--frame-rate" => options.frame_rate = number::<u32>(&arg, args.next())?.max(1),
+                "--svg" => options.svg = Some(value(&arg, args.next())?.into()),
+                "--svg-paths" => options.svg_paths = true,
+                "--annotate" => options.annotate = true,
+                "--stats" => options.stats = Some(value(&arg, args.next())?.into()),
+                "--stats-format" => {
+                    options.stats_format =
+                        Some(StatisticsFormat::parse(&value(&arg, args.next())?)?)
+                }
+                "--no-window" => options.no_window = true,
+                "--frames" => options.frames = number::<u64>(&arg, args.next())?.max(1),
+                "--steps-per-frame" => {
+                    options.steps_per_frame = number::<u64>(&arg, args.next())?.max(1)
+                }
+                "--delay" => options.delay = number(&arg, args.next())?,
+                "--crop" => options.crop = Crop::parse(&value(&arg, args.next())?)?,
+                "--trail" => options.trail = Some(number(&arg, args.next())?),
+                "--trail-color" => options.trail_color = Some(color(&value(&arg, args.next())?)?),
+                "--generations" => options.generations = number(&arg, args.next())?,
+                "--scale" => options.scale = number::<u32>(&arg, args.next())?.max(1),
+                "--palette" => {
+                    let name = value(&arg, args.next())?;
+                    options.palette =
+                        Some(Palette::find(&name).ok_or(format!("Unknown palette \"{}\"", name))?);
+                }
+                "--overlay" => options.overlay = Some(Overlay::parse(&value(&arg, args.next())?)?),
+                "--grid" => options.grid = true,
+                "--grid-major" => {
+                    options.grid_major = Some(number::<u32>(&arg, args.next())?.max(1))
+                }
+                "--terminal" => options.terminal = true,
+                "--braille" => options.braille = true,
+                "--gpu-check" => options.gpu_check = Some(number(&arg, args.next())?),
+                "--help" | "-h" => options.help = true,
+                _ => return Err(format!("Unknown option \"{}\"\n\n{}", arg, USAGE)),
+            }
+        }
+
+        // The records would be written between the frames the terminal draws
+        if options.terminal && !options.headless() && options.stats_to_stdout() {
+            return Err(
+                "--stats - can't write to the terminal the board is drawn on, give a file instead"
+                    .to_string(),
+            );
+        }
+
+        // The records would be written between the bytes of the frames
+        if options.stats_to_stdout() && options.video.as_deref() == Some(Path::new("-")) {
+            return Err(
+                "--stats - and --video - can't both write to the standard output".to_string(),
+            );
+        }
+
+        Ok(options)
+    }
+
+    // Whether the options ask for exports only, without a window
+    pub fn headless(&self) -> bool {
+        self.no_window
+            || self.screenshot.is_some()
+            || self.svg.is_some()
+            || self.gif.is_some()
+            || self.video.is_some()
+    }
+
+    // Whether --stats writes to the standard output, which then can't take messages
+    pub fn stats_to_stdout(&self) -> bool {
+        self.stats.as_deref() == Some(Path::new("-"))
+    }
+
+    // The default style with the palette, trail, overlay and grid of the options
+    pub fn style(&self) -> Style {
+        let mut style = Style::new();
+
+        if let Some(palette) = &self.palette {
+            style.palette = palette.clone();
+        }
+        if let Some(length) = self.trail {
+            style.trail.length = length.min(crate::render::MAX_TRAIL_LENGTH);
+        }
+        if let Some(color) = self.trail_color {
+            style.trail.color = Some(color);
+        }
+        if let Some(overlay) = self.overlay {
+            style.overlay = overlay;
+        }
+        style.grid.visible = self.grid;
+        if let Some(major) = self.grid_major {
+            style.grid.major = major;
+        }
+
+        style
+    }
+
+    // The statistics of --stats, if asked for
+    pub fn statistics(&self) -> Result<Option<Statistics>, String> {
+        match &self.stats {
+            Some(path) => {
+                let format = self
+                    .stats_format
+                    .unwrap_or_else(|| StatisticsFormat::from_path(path));
+                Statistics::create(path, format).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn svg_options(&self) -> SvgOptions {
+        SvgOptions {
+            scale: self.scale,
+            merge: self.svg_paths,
+            annotate: self.annotate,
+        }
+    }
+}
+
+fn value(option: &str, value: Option<String>) -> Result<String, String> {
+    value.ok_or(format!("Missing value after {}", option))
+}
+
+fn number<T: std::str::FromStr>(option: &str, text: Option<String>) -> Result<T, String> {
+    let text = value(option, text)?;
+    text.parse()
+        .map_err(|_| format!("Invalid number \"{}\" after {}", text, option))
+}
+
+// Parses "RRGGBB" in hexadecimal, with or without a leading #
+fn color(text: &str) -> Result<glm::Vec3, String> {
+    let invalid = || format!("Invalid colour \"{}\", expected RRGGBB", text);
+
+    let digits = text.strip_prefix('#').unwrap_or(text);
+    if digits.len() != 6 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid());
+    }
+
+    let channel = |start: usize| {
+        u8::from_str_radix(&digits[start..start + 2], 16)
+            .map(|byte| byte as f32 / 255.0)
+            .map_err(|_| invalid())
+    };
+
+    Ok(glm::vec3(channel(0)?, channel(2)?, channel(4)?))
+}
+
+// Parses "WIDTHxHEIGHT"
+fn size(text: &str) -> Result<(u32, u32), String> {
+    let invalid = || format!("Invalid size \"{}\", expected WIDTHxHEIGHT", text);
+
+    let (width, height) = match text.find('x') {
+        Some(separator) => (&text[..separator], &text[separator + 1..]),
+        None => return Err(invalid()),
+    };
+
+    match (width.parse(), height.parse()) {
+        (Ok(width), Ok(height)) if width > 0 && height > 0 => Ok((width, height)),
+        _ => Err(invalid()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_colors() {
+        assert_eq!(color("ff8000").unwrap(), glm::vec3(1.0, 128.0 / 255.0, 0.0));
+        assert_eq!(
+            color("#FF8000").unwrap(),
+            glm::vec3(1.0, 128.0 / 255.0, 0.0)
+        );
+        assert!(color("ff800").is_err());
+        assert!(color("#ff80000").is_err());
+        assert!(color("gg8000").is_err());
+        assert!(color("+f8000").is_err());
+    }
+}
@@ -0,0 +1,121 @@
+// Patterns made from images (PNG, JPEG and the other formats of the image crate), with dark
+// pixels as live cells
+
+use std::path::Path;
+
+use image::imageops::FilterType;
+
+use super::Pattern;
+
+// How grey pixels become live or dead cells
+#[derive(Clone, Copy, PartialEq)]
+pub enum Conversion {
+    // Live where the pixel is darker than the threshold, from 0 to 255
+    Threshold(u8),
+    // Floyd–Steinberg error diffusion, so the density of live cells follows the shades
+    Dither,
+}
+
+// Loads an image and scales it to fit in `max_width` x `max_height` cells, keeping its aspect
+// ratio. Transparent pixels are dead, and `invert` makes light pixels alive instead
+pub fn import(
+    path: &Path,
+    (max_width, max_height): (u32, u32),
+    conversion: Conversion,
+    invert: bool,
+) -> Result<Pattern, String> {
+    let image = image::open(path).map_err(|e| e.to_string())?;
+    let image = image
+        .resize(max_width, max_height, FilterType::Triangle)
+        .into_luma_alpha8();
+
+    let (width, height) = image.dimensions();
+
+    let mut darkness: Vec<f32> = image
+        .pixels()
+        .map(|pixel| {
+            let [luma, alpha] = pixel.0;
+            darkness(luma, alpha, invert)
+        })
+        .collect();
+
+    let mut pattern = Pattern::new(width, height);
+
+    match conversion {
+        Conversion::Threshold(threshold) => {
+            let threshold = 1.0 - threshold as f32 / 255.0;
+
+            for y in 0..height {
+                for x in 0..width {
+                    pattern.set(x, y, darkness[(y * width + x) as usize] > threshold);
+                }
+            }
+        }
+        Conversion::Dither => {
+            for y in 0..height {
+                for x in 0..width {
+                    // Transparent pixels stay dead, without spreading any error
+                    if image.get_pixel(x, y).0[1] == 0 {
+                        continue;
+                    }
+
+                    let index = (y * width + x) as usize;
+                    let alive = darkness[index] > 0.5;
+                    let error = darkness[index] - if alive { 1.0 } else { 0.0 };
+                    pattern.set(x, y, alive);
+
+                    // Spread the error on the pixels not converted yet
+                    let mut spread = |dx: i32, dy: u32, weight: f32| {
+                        let (x, y) = (x as i32 + dx, y + dy);
+
+                        if x >= 0 && (x as u32) < width && y < height {
+                            darkness[(y * width + x as u32) as usize] += error * weight;
+                        }
+                    };
+                    spread(1, 0, 7.0 / 16.0);
+                    spread(-1, 1, 3.0 / 16.0);
+                    spread(0, 1, 5.0 / 16.0);
+                    spread(1, 1, 1.0 / 16.0);
+                }
+            }
+        }
+    }
+
+    Ok(pattern)
+}
+
+// Darkness of a pixel, from 0 for white to 1 for black, or the other way round when inverted.
+// Partly transparent pixels are lighter, and transparent ones have no darkness either way
+fn darkness(luma: u8, alpha: u8, invert: bool) -> f32 {
+    let luma = luma as f32 / 255.0;
+    let darkness = if invert { luma } else { 1.0 - luma };
+
+    darkness * alpha as f32 / 255.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transparent_pixels_have_no_darkness() {
+        for &luma in &[0, 128, 255] {
+            assert_eq!(darkness(luma, 0, false), 0.0);
+            assert_eq!(darkness(luma, 0, true), 0.0);
+        }
+    }
+
+    #[test]
+    fn inverts_opaque_pixels() {
+        assert_eq!(darkness(0, 255, false), 1.0);
+        assert_eq!(darkness(255, 255, false), 0.0);
+        assert_eq!(darkness(0, 255, true), 0.0);
+        assert_eq!(darkness(255, 255, true), 1.0);
+    }
+
+    #[test]
+    fn lightens_partly_transparent_pixels() {
+        assert!((darkness(0, 51, false) - 0.2).abs() < 1e-6);
+        assert!((darkness(255, 51, true) - 0.2).abs() < 1e-6);
+    }
+}
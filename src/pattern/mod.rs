@@ -0,0 +1,68 @@
+pub mod bitmap;
+pub mod rle;
+
+// A rectangle of cells detached from the board, as used by the clipboard.
+// Rows go from top to bottom, like in RLE files
+#[derive(Clone, PartialEq)]
+pub struct Pattern {
+    pub width: u32,
+    pub height: u32,
+    pub cells: Vec<bool>,
+}
+
+impl Pattern {
+    pub fn new(width: u32, height: u32) -> Self {
+        Pattern {
+            width,
+            height,
+            cells: vec![false; (width * height) as usize],
+        }
+    }
+
+    pub fn at(&self, x: u32, y: u32) -> bool {
+        self.cells[crate::index(x, y, self.width) as usize]
+    }
+
+    pub fn set(&mut self, x: u32, y: u32, value: bool) {
+        self.cells[crate::index(x, y, self.width) as usize] = value;
+    }
+
+    // Builds a pattern of the given size from the value of each of its cells
+    fn from_fn(width: u32, height: u32, cell: impl Fn(u32, u32) -> bool) -> Self {
+        let mut pattern = Pattern::new(width, height);
+
+        for y in 0..height {
+            for x in 0..width {
+                pattern.set(x, y, cell(x, y));
+            }
+        }
+
+        pattern
+    }
+
+    pub fn rotate_clockwise(&self) -> Self {
+        Pattern::from_fn(self.height, self.width, |x, y| {
+            self.at(y, self.height - 1 - x)
+        })
+    }
+
+    pub fn rotate_counter_clockwise(&self) -> Self {
+        Pattern::from_fn(self.height, self.width, |x, y| {
+            self.at(self.width - 1 - y, x)
+        })
+    }
+
+    // Mirrors left and right
+    pub fn flip_horizontal(&self) -> Self {
+        Pattern::from_fn(self.width, self.height, |x, y| {
+            self.at(self.width - 1 - x, y)
+        })
+    }
+
+    // Mirrors top and bottom
+    pub fn flip_vertical(&self) -> Self {
+        Pattern::from_fn(self.width, self.height, |x, y| {
+            self.at(x, self.height - 1 - y)
+        })
+    }
+}
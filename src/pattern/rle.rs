@@ -0,0 +1,289 @@
+// Run Length Encoded patterns, the format used by Golly and the LifeWiki
+// (https://www.conwaylife.com/wiki/Run_Length_Encoded)
+
+use super::Pattern;
+
+// Golly and most tools keep lines at most this long
+const MAX_LINE_LENGTH: usize = 70;
+
+pub fn encode(pattern: &Pattern) -> String {
+    let mut body = String::new();
+    // Ends of row not written yet, as trailing empty rows are omitted
+    let mut pending_rows = 0;
+
+    for y in 0..pattern.height {
+        let row: Vec<bool> = (0..pattern.width).map(|x| pattern.at(x, y)).collect();
+
+        // Trailing dead cells are implied by the end of row
+        let length = row
+            .iter()
+            .rposition(|&cell| cell)
+            .map_or(0, |last| last + 1);
+
+        if length == 0 {
+            pending_rows += 1;
+            continue;
+        }
+
+        if pending_rows > 0 {
+            push_run(&mut body, pending_rows, '$');
+        }
+
+        let mut x = 0;
+        while x < length {
+            let run = row[x..length]
+                .iter()
+                .take_while(|&&cell| cell == row[x])
+                .count();
+            push_run(&mut body, run, if row[x] { 'o' } else { 'b' });
+            x += run;
+        }
+
+        pending_rows = 1;
+    }
+
+    body.push('!');
+
+    let mut rle = format!(
+        "x = {}, y = {}, rule = B3/S23\n",
+        pattern.width, pattern.height
+    );
+
+    // Wrap the body without splitting a run from its count
+    let mut line_length = 0;
+    for item in split_runs(&body) {
+        if line_length + item.len() > MAX_LINE_LENGTH {
+            rle.push('\n');
+            line_length = 0;
+        }
+        rle.push_str(item);
+        line_length += item.len();
+    }
+    rle.push('\n');
+
+    rle
+}
+
+fn push_run(body: &mut String, count: usize, tag: char) {
+    if count > 1 {
+        body.push_str(&count.to_string());
+    }
+    body.push(tag);
+}
+
+// Splits an RLE body into runs: an optional count followed by its tag
+fn split_runs(body: &str) -> Vec<&str> {
+    let mut runs = Vec::new();
+    let mut start = 0;
+
+    for (i, c) in body.char_indices() {
+        if !c.is_ascii_digit() {
+            runs.push(&body[start..=i]);
+            start = i + 1;
+        }
+    }
+
+    runs
+}
+
+// Reads a pattern of at most `max_width` x `max_height` cells, so clipboards and files of any
+// size are rejected before anything is allocated for them
+pub fn decode(text: &str, (max_width, max_height): (u32, u32)) -> Result<Pattern, String> {
+    let too_large = || {
+        format!(
+            "The pattern doesn't fit in {}x{} cells",
+            max_width, max_height
+        )
+    };
+
+    let mut header_size = None;
+    let mut cells = Vec::new();
+
+    let (mut x, mut y) = (0, 0);
+    let mut count = String::new();
+
+    'lines: for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if header_size.is_none() && cells.is_empty() && line.starts_with('x') {
+            let (width, height) = parse_header(line)?;
+            if width > max_width || height > max_height {
+                return Err(too_large());
+            }
+
+            header_size = Some((width, height));
+            continue;
+        }
+
+        for c in line.chars() {
+            if c.is_ascii_digit() {
+                count.push(c);
+                continue;
+            }
+
+            if c.is_whitespace() {
+                continue;
+            }
+
+            let run: u32 = if count.is_empty() {
+                1
+            } else {
+                count
+                    .parse()
+                    .map_err(|_| format!("Invalid run length {}", count))?
+            };
+            count.clear();
+
+            // Runs past the end of u32 can't be in a pattern that fits
+            let after = |start: u32| start.checked_add(run).ok_or_else(too_large);
+
+            match c {
+                'b' | '.' => x = after(x)?,
+                '$' => {
+                    x = 0;
+                    y = after(y)?;
+                }
+                '!' => break 'lines,
+                // Other letters are the states of multi-state rules, all alive here
+                c if c.is_ascii_alphabetic() => {
+                    let end = after(x)?;
+                    if end > max_width || y >= max_height {
+                        return Err(too_large());
+                    }
+
+                    cells.extend((x..end).map(|x| (x, y)));
+                    x = end;
+                }
+                c => return Err(format!("Unexpected character '{}' in RLE", c)),
+            }
+        }
+    }
+
+    // Some tools omit the header, so the size is also taken from the cells themselves
+    let (mut width, mut height) = header_size.unwrap_or((0, 0));
+    for &(x, y) in &cells {
+        width = width.max(x + 1);
+        height = height.max(y + 1);
+    }
+
+    if width == 0 || height == 0 {
+        return Err("The RLE pattern is empty".to_string());
+    }
+
+    let mut pattern = Pattern::new(width, height);
+    for (x, y) in cells {
+        pattern.set(x, y, true);
+    }
+
+    Ok(pattern)
+}
+
+// Reads the size from a header line such as "x = 3, y = 3, rule = B3/S23"
+fn parse_header(line: &str) -> Result<(u32, u32), String> {
+    let mut width = None;
+    let mut height = None;
+
+    for field in line.split(',') {
+        let mut parts = field.splitn(2, '=').map(str::trim);
+
+        match (parts.next(), parts.next()) {
+            (Some("x"), Some(value)) => width = value.parse().ok(),
+            (Some("y"), Some(value)) => height = value.parse().ok(),
+            _ => {}
+        }
+    }
+
+    match (width, height) {
+        (Some(width), Some(height)) => Ok((width, height)),
+        _ => Err(format!("Invalid RLE header \"{}\"", line)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMIT: (u32, u32) = (100, 100);
+
+    fn pattern(rows: &[&str]) -> Pattern {
+        let mut pattern = Pattern::new(rows[0].len() as u32, rows.len() as u32);
+
+        for (y, row) in rows.iter().enumerate() {
+            for (x, cell) in row.chars().enumerate() {
+                pattern.set(x as u32, y as u32, cell == 'o');
+            }
+        }
+
+        pattern
+    }
+
+    #[test]
+    fn decodes_a_glider() {
+        let glider = decode(
+            "#N Glider\nx = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n",
+            LIMIT,
+        )
+        .unwrap();
+
+        assert!(glider == pattern(&[".o.", "..o", "ooo"]));
+    }
+
+    #[test]
+    fn round_trips() {
+        let patterns = [
+            pattern(&["o"]),
+            pattern(&[".o.", "..o", "ooo"]),
+            // Empty rows inside and at the end, and dead cells at the end of rows
+            pattern(&["oo....", "......", "......", "o.o.o.", "......"]),
+            pattern(&["..........", "...oooo...", ".........."]),
+            // Long enough to wrap the lines of the body
+            pattern(&["o.".repeat(50).as_str(), ".o".repeat(50).as_str()]),
+        ];
+
+        for pattern in &patterns {
+            let decoded = decode(&encode(pattern), LIMIT).unwrap();
+
+            assert!(decoded == *pattern, "{}", encode(pattern));
+        }
+    }
+
+    #[test]
+    fn takes_the_size_from_the_cells_without_a_header() {
+        let decoded = decode("2o$obo!", LIMIT).unwrap();
+
+        assert!(decoded == pattern(&["oo.", "o.o"]));
+    }
+
+    #[test]
+    fn rejects_oversized_headers() {
+        assert!(decode("x = 70000, y = 70000\no!", LIMIT).is_err());
+        assert!(decode("x = 101, y = 1\no!", LIMIT).is_err());
+        assert!(decode("x = 100, y = 100\no!", LIMIT).is_ok());
+    }
+
+    #[test]
+    fn rejects_cells_beyond_the_limit() {
+        assert!(decode("101o!", LIMIT).is_err());
+        assert!(decode("100bo!", LIMIT).is_err());
+        assert!(decode("100$o!", LIMIT).is_err());
+        assert!(decode("99bo$98$o!", LIMIT).is_ok());
+    }
+
+    #[test]
+    fn rejects_overflowing_runs() {
+        assert!(decode("4294967295o!", LIMIT).is_err());
+        assert!(decode("4294967295b2o!", LIMIT).is_err());
+        assert!(decode("4294967295$4294967295$o!", LIMIT).is_err());
+        assert!(decode("99999999999o!", LIMIT).is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        assert!(decode("", LIMIT).is_err());
+        assert!(decode("x = 3\nbo!", LIMIT).is_err());
+        assert!(decode("bo$2bo$3o%!", LIMIT).is_err());
+        assert!(decode("!", LIMIT).is_err());
+    }
+}
@@ -0,0 +1,84 @@
+use std::time::Duration;
+
+const MIN_STEPS_PER_SECOND: f64 = 0.5;
+const MAX_STEPS_PER_SECOND: f64 = 15_360.0;
+
+// Decides how many generations to compute each frame, so the simulation rate doesn't depend on
+// the render rate (fixed timestep)
+pub struct Playback {
+    pub playing: bool,
+    pub steps_per_second: f64,
+    // Simulation time not yet turned into generations, in steps
+    accumulator: f64,
+    // Generation to run to as fast as possible before pausing
+    pub target_generation: Option<u64>,
+    // Single steps requested with the step key
+    pending_steps: u64,
+}
+
+impl Playback {
+    pub fn new(steps_per_second: f64) -> Self {
+        Playback {
+            playing: true,
+            steps_per_second,
+            accumulator: 0.0,
+            target_generation: None,
+            pending_steps: 0,
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.playing = !self.playing;
+        self.target_generation = None;
+        self.accumulator = 0.0;
+    }
+
+    // Pauses and advances by exactly one generation
+    pub fn step(&mut self) {
+        self.playing = false;
+        self.target_generation = None;
+        self.pending_steps += 1;
+    }
+
+    pub fn faster(&mut self) {
+        self.steps_per_second = (self.steps_per_second * 2.0).min(MAX_STEPS_PER_SECOND);
+    }
+
+    pub fn slower(&mut self) {
+        self.steps_per_second = (self.steps_per_second / 2.0).max(MIN_STEPS_PER_SECOND);
+    }
+
+    pub fn run_to(&mut self, generation: u64) {
+        self.target_generation = Some(generation);
+    }
+
+    // Number of generations to compute for a frame that lasted `elapsed`
+    pub fn steps(&mut self, elapsed: Duration, generation: u64) -> u64 {
+        if let Some(target) = self.target_generation {
+            if generation < target {
+                return target - generation;
+            }
+
+            self.target_generation = None;
+            self.playing = false;
+        }
+
+        let steps = std::mem::replace(&mut self.pending_steps, 0);
+
+        if !self.playing {
+            return steps;
+        }
+
+        self.accumulator += elapsed.as_secs_f64() * self.steps_per_second;
+        let whole_steps = self.accumulator.floor();
+        self.accumulator -= whole_steps;
+
+        steps + whole_steps as u64
+    }
+
+    // Forgets the generations a frame had no time to compute, so a slow machine doesn't build up
+    // an ever growing backlog
+    pub fn drop_backlog(&mut self) {
+        self.accumulator = 0.0;
+    }
+}
@@ -0,0 +1,136 @@
+use std::collections::VecDeque;
+
+use crate::GameOfLife;
+
+// Last generation of the cells that were never alive
+const NEVER: u64 = u64::MAX;
+
+// Bounds of the heat window, in generations
+pub const MIN_HEAT_WINDOW: u64 = 8;
+pub const MAX_HEAT_WINDOW: u64 = 1024;
+
+// What each cell did in the generations seen so far, for the colourings that need more than the
+// current board
+pub struct Activity {
+    // Cells of the last board seen
+    previous: Vec<bool>,
+    // Generation each live cell was born in
+    alive_since: Vec<u64>,
+    // Last generation each dead cell was seen alive in, or NEVER
+    last_alive: Vec<u64>,
+    // Number of times each cell changed in the heat window
+    toggles: Vec<u32>,
+    // Generation of each board seen in the heat window, with the cells that changed in it
+    recent_toggles: VecDeque<(u64, Vec<u32>)>,
+    // Generations over which the changes are counted
+    heat_window: u64,
+    // Generation and edit count of the last board seen
+    seen: Option<(u64, u64)>,
+}
+
+impl Activity {
+    pub fn new(width: u32, height: u32) -> Self {
+        let size = (width * height) as usize;
+
+        Activity {
+            previous: vec![false; size],
+            alive_since: vec![0; size],
+            last_alive: vec![NEVER; size],
+            toggles: vec![0; size],
+            recent_toggles: VecDeque::new(),
+            heat_window: 64,
+            seen: None,
+        }
+    }
+
+    // Records the changes since the last board seen. Generations that aren't seen, as when the
+    // simulation runs on the GPU, count as a single change of their cells
+    pub fn observe(&mut self, board: &GameOfLife) {
+        if self.seen == Some((board.generation, board.edits)) {
+            return;
+        }
+        let previous_generation = self
+            .seen
+            .map_or(board.generation, |(generation, _)| generation);
+        self.seen = Some((board.generation, board.edits));
+
+        let mut toggled = Vec::new();
+
+        for (index, (&&alive, previous)) in board
+            .simulation
+            .iter()
+            .zip(self.previous.iter_mut())
+            .enumerate()
+        {
+            if alive != *previous {
+                *previous = alive;
+                toggled.push(index as u32);
+                self.toggles[index] += 1;
+
+                if alive {
+                    self.alive_since[index] = board.generation;
+                } else {
+                    self.last_alive[index] = previous_generation;
+                }
+            }
+        }
+
+        self.recent_toggles.push_back((board.generation, toggled));
+        self.forget_before(board.generation);
+    }
+
+    pub fn heat_window(&self) -> u64 {
+        self.heat_window
+    }
+
+    pub fn set_heat_window(&mut self, heat_window: u64) {
+        self.heat_window = heat_window.clamp(MIN_HEAT_WINDOW, MAX_HEAT_WINDOW);
+
+        if let Some((generation, _)) = self.seen {
+            self.forget_before(generation);
+        }
+    }
+
+    // Drops the changes that fell out of the heat window ending at `generation`
+    fn forget_before(&mut self, generation: u64) {
+        while let Some((oldest, _)) = self.recent_toggles.front() {
+            if oldest + self.heat_window > generation {
+                break;
+            }
+
+            if let Some((_, toggled)) = self.recent_toggles.pop_front() {
+                for index in toggled {
+                    self.toggles[index as usize] -= 1;
+                }
+            }
+        }
+    }
+
+    // Generations the cell at `index` has been alive for, if it is alive in `board`
+    pub fn age(&self, board: &GameOfLife, index: usize) -> Option<u64> {
+        if *board.simulation[index] {
+            Some(board.generation - self.alive_since[index])
+        } else {
+            None
+        }
+    }
+
+    // Generations since the cell at `index` was last alive: 0 if it is alive in `board`, and
+    // None if it never was
+    pub fn generations_since_alive(&self, board: &GameOfLife, index: usize) -> Option<u64> {
+        if *board.simulation[index] {
+            return Some(0);
+        }
+
+        match self.last_alive[index] {
+            NEVER => None,
+            // At least 1, as a cell can be edited away in the generation it was alive in
+            last_alive => Some(board.generation.saturating_sub(last_alive).max(1)),
+        }
+    }
+
+    // Changes of the cell at `index` in the heat window
+    pub fn toggles(&self, index: usize) -> u32 {
+        self.toggles[index]
+    }
+}
@@ -0,0 +1,628 @@
+use crate::objects::{Class, Object};
+use crate::pattern::Pattern;
+use crate::shaders;
+use crate::GameOfLife;
+use nalgebra_glm as glm;
+
+pub mod activity;
+pub mod palette;
+pub mod software;
+
+pub use activity::Activity;
+pub use palette::Palette;
+pub use software::SoftwareRenderer;
+
+// Highlights of a cell in the highlight texture. The lowest 3 bits are the class of the object
+// the cell is part of, 0 for none, and the next 3 bits the class of the bounding box drawn over it
+const BOX_CLASS_SHIFT: u8 = 3;
+// Cells under the pattern being pasted, alive or dead in the pattern
+const PASTE_ALIVE: u8 = 64;
+const PASTE_DEAD: u8 = 128;
+
+// Trail state of the cells that weren't alive in the length of the trail
+const FORGOTTEN: u8 = 255;
+
+// What renderers draw: the board, read only, with what is shown over it. `activity` must have
+// seen the board
+pub struct Scene<'a> {
+    pub board: &'a GameOfLife<'a>,
+    pub activity: &'a Activity,
+    pub objects: &'a [Object],
+    // Selected rectangle as (min_x, min_y, max_x, max_y)
+    pub selection: Option<(u32, u32, u32, u32)>,
+}
+
+// Draws the board, on the GPU in the window or on the CPU in an image, with the colours of
+// shaders/board.frag. The OpenGL renderer draws through the camera and adds the paste preview of
+// its view, so the images only match when it shows the cells of the software renderer at its scale
+pub trait Renderer {
+    fn render(&mut self, scene: &Scene);
+
+    // The pixels drawn by the last `render`, top row first
+    fn read_pixels(&self) -> image::RgbaImage;
+}
+
+// Texture holding one unsigned byte per cell, read with texelFetch by the board shader
+struct CellTexture {
+    id: u32,
+    width: u32,
+    height: u32,
+}
+
+impl Drop for CellTexture {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteTextures(1, &self.id);
+        }
+    }
+}
+
+impl CellTexture {
+    fn new(width: u32, height: u32) -> Self {
+        let mut id = 0;
+
+        unsafe {
+            gl::GenTextures(1, &mut id);
+            gl::BindTexture(gl::TEXTURE_2D, id);
+
+            // Integer textures can't be filtered
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32);
+
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                gl::R8UI as i32,
+                width as i32,
+                height as i32,
+                0,
+                gl::RED_INTEGER,
+                gl::UNSIGNED_BYTE,
+                std::ptr::null(),
+            );
+        }
+
+        CellTexture { id, width, height }
+    }
+
+    fn update(&self, cells: &[u8]) {
+        debug_assert_eq!(cells.len(), (self.width * self.height) as usize);
+
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, self.id);
+            gl::TexSubImage2D(
+                gl::TEXTURE_2D,
+                0,
+                0,
+                0,
+                self.width as i32,
+                self.height as i32,
+                gl::RED_INTEGER,
+                gl::UNSIGNED_BYTE,
+                cells.as_ptr() as *const std::ffi::c_void,
+            );
+        }
+    }
+}
+
+// What the colour of a cell shows
+#[derive(Clone, Copy, PartialEq)]
+pub enum Coloring {
+    // Live cells, and the cells that were alive in the last generations
+    Trail,
+    // Generations each live cell has been alive for
+    Age,
+    // Number of times each cell changed in the heat window
+    Heat,
+}
+
+impl Coloring {
+    pub fn name(self) -> &'static str {
+        match self {
+            Coloring::Trail => "Trail",
+            Coloring::Age => "Age",
+            Coloring::Heat => "Heat",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            Coloring::Trail => Coloring::Age,
+            Coloring::Age => Coloring::Heat,
+            Coloring::Heat => Coloring::Trail,
+        }
+    }
+}
+
+// How the colour of a trail fades from the trail colour to the dead colour
+#[derive(Clone, Copy, PartialEq)]
+pub enum Decay {
+    Linear,
+    // Fast at first, then slowly
+    Exponential,
+    // Slowly at first, then fast
+    Smooth,
+    // Full colour until the end of the trail
+    Constant,
+}
+
+impl Decay {
+    pub fn name(self) -> &'static str {
+        match self {
+            Decay::Linear => "linear",
+            Decay::Exponential => "exponential",
+            Decay::Smooth => "smooth",
+            Decay::Constant => "constant",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            Decay::Linear => Decay::Exponential,
+            Decay::Exponential => Decay::Smooth,
+            Decay::Smooth => Decay::Constant,
+            Decay::Constant => Decay::Linear,
+        }
+    }
+}
+
+pub const MAX_TRAIL_LENGTH: u64 = 4096;
+
+// Cells that died in the last generations, drawn fading towards the dead colour
+#[derive(Clone, Copy)]
+pub struct Trail {
+    // Generations a cell stays in the trail after it was last alive, 0 for no trail
+    pub length: u64,
+    pub decay: Decay,
+    // Colour of the cells that just died, from the palette when None
+    pub color: Option<glm::Vec3>,
+}
+
+// How much of the objects on the board is shown
+#[derive(Clone, Copy, PartialEq)]
+pub enum Overlay {
+    Hidden,
+    // Live cells coloured by the class of their object
+    Classes,
+    // And a box around each object
+    Boxes,
+    // And a label next to each box
+    Labels,
+}
+
+impl Overlay {
+    pub fn next(self) -> Self {
+        match self {
+            Overlay::Hidden => Overlay::Classes,
+            Overlay::Classes => Overlay::Boxes,
+            Overlay::Boxes => Overlay::Labels,
+            Overlay::Labels => Overlay::Hidden,
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        match text {
+            "hidden" => Ok(Overlay::Hidden),
+            "classes" => Ok(Overlay::Classes),
+            "boxes" => Ok(Overlay::Boxes),
+            "labels" => Ok(Overlay::Labels),
+            _ => Err(format!(
+                "Unknown overlay \"{}\", expected hidden, classes, boxes or labels",
+                text
+            )),
+        }
+    }
+}
+
+// Value of a class in the highlight texture
+fn class_highlight(class: Class) -> u8 {
+    class.index() as u8 + 1
+}
+
+// Lines between the cells, drawn when zoomed in enough to tell them apart
+#[derive(Clone, Copy)]
+pub struct Grid {
+    pub visible: bool,
+    // Cells between major lines, as in Golly
+    pub major: u32,
+}
+
+// Bounds of the age range, in generations
+pub const MIN_AGE_RANGE: u64 = 8;
+pub const MAX_AGE_RANGE: u64 = 4096;
+
+// How the board is coloured, by the board shader and by the software renderer
+#[derive(Clone)]
+pub struct Style {
+    pub palette: Palette,
+    pub grid: Grid,
+    pub overlay: Overlay,
+    pub coloring: Coloring,
+    // Age shown with the last colour of the gradient, in generations
+    pub age_range: u64,
+    pub trail: Trail,
+}
+
+impl Style {
+    pub fn new() -> Self {
+        Style {
+            palette: Palette::classic(),
+            grid: Grid {
+                visible: false,
+                major: 10,
+            },
+            overlay: Overlay::Classes,
+            coloring: Coloring::Trail,
+            age_range: 100,
+            trail: Trail {
+                length: 10,
+                decay: Decay::Linear,
+                color: None,
+            },
+        }
+    }
+
+    // Value of each cell in the state texture. `activity` must have seen the board
+    pub fn cell_values(&self, board: &GameOfLife, activity: &Activity) -> Vec<u8> {
+        let size = board.simulation.len();
+        let trail_length = self.trail.length;
+
+        match self.coloring {
+            // 0 for live cells, then 1 to 254 along the trail and FORGOTTEN past it, so the
+            // length of the trail doesn't change the cost of drawing it
+            Coloring::Trail => (0..size)
+                .map(
+                    |index| match activity.generations_since_alive(board, index) {
+                        Some(0) => 0,
+                        Some(generations) if generations <= trail_length => {
+                            1 + ((generations - 1) * 254 / trail_length) as u8
+                        }
+                        _ => FORGOTTEN,
+                    },
+                )
+                .collect(),
+            // 0 for dead cells, then 1 to 255 along the age range
+            Coloring::Age => (0..size)
+                .map(|index| match activity.age(board, index) {
+                    Some(age) => 1 + (age.min(self.age_range) * 254 / self.age_range) as u8,
+                    None => 0,
+                })
+                .collect(),
+            // 0 to 255 from no change to a change every generation of the window
+            Coloring::Heat => (0..size)
+                .map(|index| {
+                    let toggles = activity.toggles(index) as u64;
+                    let window = activity.heat_window();
+                    (toggles.min(window) * 255 / window) as u8
+                })
+                .collect(),
+        }
+    }
+
+    // Highlights of the objects of a board of the given size, as shown by the overlay
+    pub fn object_highlights(&self, width: u32, height: u32, objects: &[Object]) -> Vec<u8> {
+        let mut highlights = vec![0; (width * height) as usize];
+
+        if self.overlay != Overlay::Hidden {
+            for object in objects {
+                let class = class_highlight(object.class);
+
+                for &(x, y) in &object.cells {
+                    highlights[crate::index(x, y, width) as usize] |= class;
+                }
+            }
+        }
+
+        if self.overlay == Overlay::Boxes || self.overlay == Overlay::Labels {
+            for object in objects {
+                outline(&mut highlights, width, height, object);
+            }
+        }
+
+        highlights
+    }
+}
+
+// Marks the cells around the bounding box of an object
+fn outline(highlights: &mut [u8], width: u32, height: u32, object: &Object) {
+    let (origin_x, origin_y) = object.origin;
+    let highlight = class_highlight(object.class) << BOX_CLASS_SHIFT;
+
+    // From one cell before the box to one cell after it, wrapping around the board
+    let box_width = object.size.0 + 2;
+    let box_height = object.size.1 + 2;
+
+    for y in 0..box_height {
+        for x in 0..box_width {
+            if x != 0 && x != box_width - 1 && y != 0 && y != box_height - 1 {
+                continue;
+            }
+
+            let cell_x = (origin_x + width + x - 1) % width;
+            let cell_y = (origin_y + height + y - 1) % height;
+            let index = crate::index(cell_x, cell_y, width) as usize;
+
+            // The first box drawn over a cell stays
+            if highlights[index] >> BOX_CLASS_SHIFT == 0 {
+                highlights[index] |= highlight;
+            }
+        }
+    }
+}
+
+// The board as seen by the board shader: a state texture with one value per cell, depending on
+// the colouring, and a texture of highlight flags. The colours are chosen by the shader, so the
+// textures only change when the board or the highlights do
+pub struct BoardView {
+    pub palette: Palette,
+    pub grid: Grid,
+    overlay: Overlay,
+    coloring: Coloring,
+    // Age shown with the last colour of the gradient, in generations
+    age_range: u64,
+    trail: Trail,
+    state: CellTexture,
+    highlights: CellTexture,
+    // Highlights of the objects on the board
+    objects: Vec<u8>,
+    // Generation and edit count of the board in the state texture
+    shown_board: Option<(u64, u64)>,
+    // Pattern and origin of the paste preview in the highlight texture
+    shown_paste: Option<(Pattern, (u32, u32))>,
+}
+
+impl BoardView {
+    pub fn new(width: u32, height: u32) -> Self {
+        let style = Style::new();
+
+        BoardView {
+            palette: style.palette,
+            grid: style.grid,
+            overlay: style.overlay,
+            coloring: style.coloring,
+            age_range: style.age_range,
+            trail: style.trail,
+            state: CellTexture::new(width, height),
+            highlights: CellTexture::new(width, height),
+            objects: vec![0; (width * height) as usize],
+            shown_board: None,
+            shown_paste: None,
+        }
+    }
+
+    // The settings of the view, for the software renderer
+    pub fn style(&self) -> Style {
+        Style {
+            palette: self.palette.clone(),
+            grid: self.grid,
+            overlay: self.overlay,
+            coloring: self.coloring,
+            age_range: self.age_range,
+            trail: self.trail,
+        }
+    }
+
+    pub fn overlay(&self) -> Overlay {
+        self.overlay
+    }
+
+    pub fn set_overlay(&mut self, overlay: Overlay) {
+        self.overlay = overlay;
+        self.shown_board = None;
+    }
+
+    pub fn coloring(&self) -> Coloring {
+        self.coloring
+    }
+
+    pub fn set_coloring(&mut self, coloring: Coloring) {
+        self.coloring = coloring;
+        self.shown_board = None;
+    }
+
+    pub fn trail(&self) -> Trail {
+        self.trail
+    }
+
+    pub fn set_trail(&mut self, trail: Trail) {
+        self.trail = Trail {
+            length: trail.length.min(MAX_TRAIL_LENGTH),
+            ..trail
+        };
+        self.shown_board = None;
+    }
+
+    pub fn age_range(&self) -> u64 {
+        self.age_range
+    }
+
+    pub fn set_age_range(&mut self, age_range: u64) {
+        self.age_range = age_range.clamp(MIN_AGE_RANGE, MAX_AGE_RANGE);
+        self.shown_board = None;
+    }
+
+    // Uploads the board again on the next `set_board`, for changes the board itself doesn't show
+    pub fn refresh(&mut self) {
+        self.shown_board = None;
+    }
+
+    // Whether the textures already show this board
+    pub fn shows(&self, board: &GameOfLife) -> bool {
+        self.shown_board == Some((board.generation, board.edits))
+    }
+
+    // Uploads the board with the objects found on it. `activity` must have seen the board
+    pub fn set_board(&mut self, board: &GameOfLife, activity: &Activity, objects: &[Object]) {
+        let style = self.style();
+
+        self.state.update(&style.cell_values(board, activity));
+        self.objects = style.object_highlights(self.state.width, self.state.height, objects);
+
+        self.shown_board = Some((board.generation, board.edits));
+        self.upload_highlights();
+    }
+
+    // Shows `pattern` with its top left corner on `origin`, wrapping around the edges of the board
+    pub fn set_paste_preview(&mut self, preview: Option<(&Pattern, (u32, u32))>) {
+        let unchanged = match (&self.shown_paste, preview) {
+            (Some((shown, shown_origin)), Some((pattern, origin))) => {
+                shown == pattern && *shown_origin == origin
+            }
+            (None, None) => true,
+            _ => false,
+        };
+
+        if !unchanged {
+            self.shown_paste = preview.map(|(pattern, origin)| (pattern.clone(), origin));
+            self.upload_highlights();
+        }
+    }
+
+    fn upload_highlights(&self) {
+        let mut highlights = self.objects.clone();
+
+        if let Some((pattern, (origin_x, origin_y))) = &self.shown_paste {
+            for y in 0..pattern.height {
+                for x in 0..pattern.width {
+                    let cell_x = (origin_x + x) % self.highlights.width;
+                    let cell_y = (origin_y + y) % self.highlights.height;
+                    let index = crate::index(cell_x, cell_y, self.highlights.width) as usize;
+
+                    highlights[index] |= if pattern.at(x, y) {
+                        PASTE_ALIVE
+                    } else {
+                        PASTE_DEAD
+                    };
+                }
+            }
+        }
+
+        self.highlights.update(&highlights);
+    }
+
+    // Draws the board with the unit square in `vao`. `selection` is the selected rectangle as
+    // (min_x, min_y, max_x, max_y)
+    pub fn draw(
+        &self,
+        shader_program: &shaders::ShaderProgram,
+        transform: glm::Mat4,
+        selection: Option<(u32, u32, u32, u32)>,
+        vao: u32,
+    ) {
+        shader_program.set_uniform("transform", transform);
+        shader_program.set_uniform("state", 0);
+        shader_program.set_uniform("highlights", 1);
+
+        shader_program.set_uniform("dead_color", self.palette.dead);
+        shader_program.set_uniform("alive_color", self.palette.alive);
+        for (class, &color) in self.palette.objects.iter().enumerate() {
+            shader_program.set_uniform(&format!("object_colors[{}]", class), color);
+        }
+        shader_program.set_uniform("selection_color", self.palette.selection);
+        shader_program.set_uniform("paste_area_color", self.palette.paste_area);
+        shader_program.set_uniform("paste_color", self.palette.paste);
+        shader_program.set_uniform("gradient_low", self.palette.gradient[0]);
+        shader_program.set_uniform("gradient_middle", self.palette.gradient[1]);
+        shader_program.set_uniform("gradient_high", self.palette.gradient[2]);
+
+        shader_program.set_uniform("grid_color", self.palette.grid);
+        // 0 hides the grid
+        let grid_major = if self.grid.visible {
+            self.grid.major.max(1)
+        } else {
+            0
+        };
+        shader_program.set_uniform("grid_major", grid_major);
+
+        let coloring: u32 = match self.coloring {
+            Coloring::Trail => 0,
+            Coloring::Age => 1,
+            Coloring::Heat => 2,
+        };
+        shader_program.set_uniform("coloring", coloring);
+
+        let decay: u32 = match self.trail.decay {
+            Decay::Linear => 0,
+            Decay::Exponential => 1,
+            Decay::Smooth => 2,
+            Decay::Constant => 3,
+        };
+        shader_program.set_uniform("trail_decay", decay);
+        shader_program.set_uniform(
+            "trail_color",
+            self.trail.color.unwrap_or(self.palette.trail),
+        );
+
+        // An empty rectangle when nothing is selected
+        let selection = match selection {
+            Some((min_x, min_y, max_x, max_y)) => {
+                glm::vec4(min_x as f32, min_y as f32, max_x as f32, max_y as f32)
+            }
+            None => glm::vec4(1.0, 1.0, 0.0, 0.0),
+        };
+        shader_program.set_uniform("selection", selection);
+
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindTexture(gl::TEXTURE_2D, self.state.id);
+            gl::ActiveTexture(gl::TEXTURE1);
+            gl::BindTexture(gl::TEXTURE_2D, self.highlights.id);
+            gl::ActiveTexture(gl::TEXTURE0);
+
+            gl::UseProgram(shader_program.id);
+            gl::BindVertexArray(vao);
+            gl::DrawElements(gl::TRIANGLES, 6, gl::UNSIGNED_INT, std::ptr::null_mut());
+        }
+    }
+}
+
+// Draws the board with the board shader, in the style of the view
+pub struct GlRenderer<'a> {
+    pub view: &'a mut BoardView,
+    pub program: &'a shaders::ShaderProgram,
+    // From the unit square in `vao` to clip space
+    pub transform: glm::Mat4,
+    pub vao: u32,
+    pub viewport: (u32, u32),
+}
+
+impl Renderer for GlRenderer<'_> {
+    fn render(&mut self, scene: &Scene) {
+        if !self.view.shows(scene.board) {
+            self.view
+                .set_board(scene.board, scene.activity, scene.objects);
+        }
+
+        self.view
+            .draw(self.program, self.transform, scene.selection, self.vao);
+    }
+
+    fn read_pixels(&self) -> image::RgbaImage {
+        read_viewport(self.viewport)
+    }
+}
+
+// Reads the pixels drawn in the viewport so far, top row first
+pub fn read_viewport((width, height): (u32, u32)) -> image::RgbaImage {
+    let mut pixels = vec![0u8; (width * height * 4) as usize];
+
+    unsafe {
+        gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+        gl::ReadPixels(
+            0,
+            0,
+            width as i32,
+            height as i32,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            pixels.as_mut_ptr() as *mut std::ffi::c_void,
+        );
+    }
+
+    // OpenGL rows start from the bottom
+    let image =
+        image::RgbaImage::from_raw(width, height, pixels).expect("The pixels fill the image");
+    image::imageops::flip_vertical(&image)
+}
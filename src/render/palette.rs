@@ -0,0 +1,127 @@
+use nalgebra_glm as glm;
+
+// Colours of the board, in RGB from 0 to 1
+#[derive(Clone)]
+pub struct Palette {
+    pub name: &'static str,
+    pub dead: glm::Vec3,
+    pub alive: glm::Vec3,
+    // Cells that just died, at the start of their trail
+    pub trail: glm::Vec3,
+    // Live cells of still lifes, oscillators, spaceships and active objects
+    pub objects: [glm::Vec3; 4],
+    // Tints mixed with the colour of the cells they cover
+    pub selection: glm::Vec3,
+    pub paste_area: glm::Vec3,
+    // Live cells of the pattern being pasted
+    pub paste: glm::Vec3,
+    // Low, middle and high colours of the age and heat colourings
+    pub gradient: [glm::Vec3; 3],
+    pub grid: glm::Vec3,
+}
+
+impl Palette {
+    pub fn classic() -> Self {
+        Palette {
+            name: "Classic",
+            dead: glm::vec3(1.0, 1.0, 1.0),
+            alive: glm::vec3(0.0, 0.0, 0.0),
+            trail: glm::vec3(0.05, 0.05, 0.05),
+            objects: [
+                glm::vec3(0.2, 0.4, 0.9),
+                glm::vec3(0.95, 0.55, 0.0),
+                glm::vec3(1.0, 0.0, 0.0),
+                glm::vec3(0.55, 0.2, 0.7),
+            ],
+            selection: glm::vec3(0.25, 0.5, 1.0),
+            paste_area: glm::vec3(0.25, 1.0, 0.25),
+            paste: glm::vec3(0.0, 0.625, 0.0),
+            gradient: [
+                glm::vec3(0.0, 0.0, 0.6),
+                glm::vec3(0.8, 0.0, 0.4),
+                glm::vec3(1.0, 0.8, 0.0),
+            ],
+            grid: glm::vec3(0.6, 0.6, 0.6),
+        }
+    }
+
+    pub fn dark() -> Self {
+        Palette {
+            name: "Dark",
+            dead: glm::vec3(0.07, 0.07, 0.09),
+            alive: glm::vec3(0.9, 0.9, 0.9),
+            trail: glm::vec3(0.2, 0.45, 0.75),
+            objects: [
+                glm::vec3(0.35, 0.55, 1.0),
+                glm::vec3(1.0, 0.65, 0.2),
+                glm::vec3(1.0, 0.35, 0.3),
+                glm::vec3(0.75, 0.45, 0.95),
+            ],
+            selection: glm::vec3(0.2, 0.4, 0.9),
+            paste_area: glm::vec3(0.2, 0.6, 0.2),
+            paste: glm::vec3(0.45, 0.95, 0.45),
+            gradient: [
+                glm::vec3(0.35, 0.05, 0.45),
+                glm::vec3(0.9, 0.3, 0.25),
+                glm::vec3(0.99, 0.95, 0.6),
+            ],
+            grid: glm::vec3(0.3, 0.3, 0.34),
+        }
+    }
+
+    // Okabe and Ito's colours, told apart with all common forms of colour blindness, and the
+    // viridis gradient
+    pub fn colour_blind() -> Self {
+        Palette {
+            name: "Colour blind",
+            dead: glm::vec3(1.0, 1.0, 1.0),
+            alive: glm::vec3(0.0, 0.0, 0.0),
+            trail: glm::vec3(0.0, 0.45, 0.7),
+            objects: [
+                glm::vec3(0.0, 0.45, 0.7),
+                glm::vec3(0.84, 0.37, 0.0),
+                glm::vec3(0.9, 0.62, 0.0),
+                glm::vec3(0.8, 0.47, 0.65),
+            ],
+            selection: glm::vec3(0.34, 0.71, 0.91),
+            paste_area: glm::vec3(0.0, 0.62, 0.45),
+            paste: glm::vec3(0.0, 0.45, 0.7),
+            gradient: [
+                glm::vec3(0.27, 0.0, 0.33),
+                glm::vec3(0.13, 0.57, 0.55),
+                glm::vec3(0.99, 0.91, 0.14),
+            ],
+            grid: glm::vec3(0.6, 0.6, 0.6),
+        }
+    }
+
+    pub fn colour_blind_dark() -> Self {
+        Palette {
+            name: "Colour blind dark",
+            dead: glm::vec3(0.07, 0.07, 0.09),
+            alive: glm::vec3(0.9, 0.9, 0.9),
+            trail: glm::vec3(0.34, 0.71, 0.91),
+            grid: glm::vec3(0.3, 0.3, 0.34),
+            ..Palette::colour_blind()
+        }
+    }
+
+    // Palettes to choose from, the default first
+    pub fn all() -> Vec<Palette> {
+        vec![
+            Palette::classic(),
+            Palette::dark(),
+            Palette::colour_blind(),
+            Palette::colour_blind_dark(),
+        ]
+    }
+
+    // Palette with the given name, ignoring case, with dashes or spaces between words
+    pub fn find(name: &str) -> Option<Palette> {
+        let name = name.replace('-', " ");
+
+        Palette::all()
+            .into_iter()
+            .find(|palette| palette.name.eq_ignore_ascii_case(&name))
+    }
+}
@@ -0,0 +1,324 @@
+// Renders the board on the CPU, with the colours of shaders/board.frag, for exports that run
+// without a window or a GPU
+
+use super::{Activity, Coloring, Decay, Renderer, Scene, Style, BOX_CLASS_SHIFT, FORGOTTEN};
+use crate::objects::Object;
+use crate::GameOfLife;
+use nalgebra_glm as glm;
+
+// Spacing of grid lines in pixels over which they fade in, as in the board shader
+const GRID_FADE_START: f32 = 4.0;
+const GRID_FADE_END: f32 = 8.0;
+
+// Draws a region of the board in an image, with `scale` pixels per cell
+pub struct SoftwareRenderer {
+    pub style: Style,
+    // Cells drawn, as (min_x, min_y, max_x, max_y)
+    pub region: (u32, u32, u32, u32),
+    pub scale: u32,
+    image: image::RgbaImage,
+}
+
+impl SoftwareRenderer {
+    pub fn new(style: Style, region: (u32, u32, u32, u32), scale: u32) -> Self {
+        SoftwareRenderer {
+            style,
+            region,
+            scale,
+            image: image::RgbaImage::new(0, 0),
+        }
+    }
+}
+
+impl Renderer for SoftwareRenderer {
+    fn render(&mut self, scene: &Scene) {
+        self.image = render(
+            &self.style,
+            scene.board,
+            scene.activity,
+            scene.objects,
+            scene.selection,
+            self.region,
+            self.scale,
+        );
+    }
+
+    fn read_pixels(&self) -> image::RgbaImage {
+        self.image.clone()
+    }
+}
+
+// Colour of a cell with the given value in the state texture and highlight flags, without the
+// grid, selection and paste tints
+pub fn cell_color(style: &Style, value: u8, flags: u8) -> glm::Vec3 {
+    let palette = &style.palette;
+    let object_class = (flags & 7) as usize;
+    let box_class = ((flags >> BOX_CLASS_SHIFT) & 7) as usize;
+
+    let mut color = palette.dead;
+
+    match style.coloring {
+        Coloring::Age => {
+            if value != 0 {
+                color = gradient(style, (value - 1) as f32 / 254.0);
+            }
+        }
+        Coloring::Heat => {
+            if value != 0 {
+                color = gradient(style, value as f32 / 255.0);
+            }
+        }
+        Coloring::Trail => {
+            if value == 0 {
+                color = if object_class != 0 {
+                    palette.objects[object_class - 1]
+                } else {
+                    palette.alive
+                };
+            } else if value != FORGOTTEN {
+                let trail_color = style.trail.color.unwrap_or(palette.trail);
+                let strength = decay(style.trail.decay, (value - 1) as f32 / 254.0);
+                color = glm::mix(&palette.dead, &trail_color, strength);
+            }
+        }
+    }
+
+    if box_class != 0 {
+        color = glm::mix(&color, &palette.objects[box_class - 1], 0.35);
+    }
+
+    color
+}
+
+// Draws the cells of `region`, given as (min_x, min_y, max_x, max_y), with `scale` pixels per
+// cell and the selected rectangle tinted. `activity` must have seen the board
+pub fn render(
+    style: &Style,
+    board: &GameOfLife,
+    activity: &Activity,
+    objects: &[Object],
+    selection: Option<(u32, u32, u32, u32)>,
+    (min_x, min_y, max_x, max_y): (u32, u32, u32, u32),
+    scale: u32,
+) -> image::RgbaImage {
+    let values = style.cell_values(board, activity);
+    let highlights = style.object_highlights(board.width, board.height, objects);
+
+    let scale = scale.max(1);
+    let width = (max_x - min_x + 1) * scale;
+    let height = (max_y - min_y + 1) * scale;
+
+    // Strength of the minor and major grid lines, fading in with the size of the cells
+    let spacing = style.grid.major.max(1);
+    let (minor, major) = if style.grid.visible {
+        (
+            0.5 * grid_visibility(scale),
+            grid_visibility(scale * spacing),
+        )
+    } else {
+        (0.0, 0.0)
+    };
+
+    image::RgbaImage::from_fn(width, height, |x, y| {
+        let cell_x = min_x + x / scale;
+        let cell_y = min_y + y / scale;
+        let index = crate::index(cell_x, cell_y, board.width) as usize;
+
+        let mut color = cell_color(style, values[index], highlights[index]);
+
+        // Lines are on the first pixel of the cells after them, as in the board shader
+        let on_line_x = x % scale == 0;
+        let on_line_y = y % scale == 0;
+        let on_major = (on_line_x && cell_x.is_multiple_of(spacing))
+            || (on_line_y && cell_y.is_multiple_of(spacing));
+
+        let line = if on_major {
+            major.max(minor)
+        } else if on_line_x || on_line_y {
+            minor
+        } else {
+            0.0
+        };
+        if line > 0.0 {
+            color = glm::mix(&color, &style.palette.grid, line);
+        }
+
+        if let Some((left, top, right, bottom)) = selection {
+            if (left..=right).contains(&cell_x) && (top..=bottom).contains(&cell_y) {
+                color = glm::mix(&color, &style.palette.selection, 0.5);
+            }
+        }
+
+        let [red, green, blue] = to_rgb(color);
+        image::Rgba([red, green, blue, 255])
+    })
+}
+
+// Bytes of a colour, as written to images, SVGs and terminals
+pub fn to_rgb(color: glm::Vec3) -> [u8; 3] {
+    let byte = |channel: f32| (channel.clamp(0.0, 1.0) * 255.0).round() as u8;
+    [byte(color.x), byte(color.y), byte(color.z)]
+}
+
+fn gradient(style: &Style, t: f32) -> glm::Vec3 {
+    let [low, middle, high] = &style.palette.gradient;
+
+    if t < 0.5 {
+        glm::mix(low, middle, t * 2.0)
+    } else {
+        glm::mix(middle, high, t * 2.0 - 1.0)
+    }
+}
+
+fn decay(decay: Decay, t: f32) -> f32 {
+    match decay {
+        Decay::Linear => 1.0 - t,
+        Decay::Exponential => ((-5.0 * t).exp() - (-5.0f32).exp()) / (1.0 - (-5.0f32).exp()),
+        Decay::Smooth => 1.0 - smoothstep(0.0, 1.0, t),
+        Decay::Constant => 1.0,
+    }
+}
+
+fn grid_visibility(line_pixels: u32) -> f32 {
+    smoothstep(GRID_FADE_START, GRID_FADE_END, line_pixels as f32)
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::Classifier;
+    use crate::render::{Overlay, Palette};
+
+    fn board(width: u32, height: u32, cells: &[(u32, u32)]) -> GameOfLife<'static> {
+        let mut board = GameOfLife::new(width, height);
+        for &(x, y) in cells {
+            board.set(x, y, true);
+        }
+        board
+    }
+
+    fn pixel(color: glm::Vec3) -> image::Rgba<u8> {
+        let [red, green, blue] = to_rgb(color);
+        image::Rgba([red, green, blue, 255])
+    }
+
+    #[test]
+    fn colours_the_objects_with_the_palette() {
+        let mut style = Style::new();
+        style.palette = Palette::find("dark").unwrap();
+
+        // A block and a blinker
+        let board = board(
+            8,
+            8,
+            &[(1, 1), (2, 1), (1, 2), (2, 2), (5, 3), (5, 4), (5, 5)],
+        );
+        let mut activity = Activity::new(8, 8);
+        activity.observe(&board);
+        let objects = Classifier::new().find_objects(&board);
+
+        let image = render(&style, &board, &activity, &objects, None, (0, 0, 7, 7), 1);
+
+        assert_eq!((image.width(), image.height()), (8, 8));
+        assert_eq!(*image.get_pixel(1, 2), pixel(style.palette.objects[0]));
+        assert_eq!(*image.get_pixel(5, 4), pixel(style.palette.objects[1]));
+        assert_eq!(*image.get_pixel(0, 0), pixel(style.palette.dead));
+
+        style.overlay = Overlay::Hidden;
+        let image = render(&style, &board, &activity, &objects, None, (0, 0, 7, 7), 1);
+
+        assert_eq!(*image.get_pixel(1, 2), pixel(style.palette.alive));
+        assert_eq!(*image.get_pixel(5, 4), pixel(style.palette.alive));
+    }
+
+    #[test]
+    fn fades_the_trail() {
+        let mut style = Style::new();
+        style.palette = Palette::find("dark").unwrap();
+        let (dead, trail) = (style.palette.dead, style.palette.trail);
+
+        // A lone cell, which dies in the first generation
+        let mut board = board(5, 5, &[(2, 2)]);
+        let mut activity = Activity::new(5, 5);
+        activity.observe(&board);
+
+        let mut trail_pixel = |generations: u64| {
+            for _ in 0..generations {
+                board = board.simulate();
+                activity.observe(&board);
+            }
+
+            *render(&style, &board, &activity, &[], None, (2, 2, 2, 2), 1).get_pixel(0, 0)
+        };
+
+        assert_eq!(trail_pixel(1), pixel(trail));
+        // Dead for 5 of the 10 generations of the trail
+        let strength = 1.0 - 101.0 / 254.0;
+        assert_eq!(trail_pixel(4), pixel(glm::mix(&dead, &trail, strength)));
+        assert_eq!(trail_pixel(6), pixel(dead));
+    }
+
+    #[test]
+    fn draws_grid_lines_on_the_first_pixel_of_the_cells() {
+        let mut style = Style::new();
+        style.grid.visible = true;
+        style.grid.major = 2;
+        let (dead, grid) = (style.palette.dead, style.palette.grid);
+
+        let board = board(4, 4, &[]);
+        let mut activity = Activity::new(4, 4);
+        activity.observe(&board);
+
+        let image = render(&style, &board, &activity, &[], None, (0, 0, 3, 3), 8);
+
+        assert_eq!((image.width(), image.height()), (32, 32));
+        assert_eq!(*image.get_pixel(0, 4), pixel(grid));
+        assert_eq!(*image.get_pixel(16, 4), pixel(grid));
+        assert_eq!(*image.get_pixel(8, 4), pixel(glm::mix(&dead, &grid, 0.5)));
+        assert_eq!(*image.get_pixel(4, 24), pixel(glm::mix(&dead, &grid, 0.5)));
+        assert_eq!(*image.get_pixel(4, 4), pixel(dead));
+
+        // Lines fade out when they are only a few pixels apart
+        let image = render(&style, &board, &activity, &[], None, (0, 0, 3, 3), 2);
+
+        assert_eq!(*image.get_pixel(0, 0), pixel(dead));
+    }
+
+    #[test]
+    fn tints_the_selection() {
+        let mut style = Style::new();
+        style.overlay = Overlay::Hidden;
+        let palette = style.palette.clone();
+
+        let board = board(4, 4, &[(1, 1)]);
+        let mut activity = Activity::new(4, 4);
+        activity.observe(&board);
+
+        // The region starts on the second column
+        let mut renderer = SoftwareRenderer::new(style, (1, 0, 3, 3), 1);
+        renderer.render(&Scene {
+            board: &board,
+            activity: &activity,
+            objects: &[],
+            selection: Some((1, 1, 2, 2)),
+        });
+        let image = renderer.read_pixels();
+
+        assert_eq!((image.width(), image.height()), (3, 4));
+        assert_eq!(
+            *image.get_pixel(0, 1),
+            pixel(glm::mix(&palette.alive, &palette.selection, 0.5))
+        );
+        assert_eq!(
+            *image.get_pixel(1, 2),
+            pixel(glm::mix(&palette.dead, &palette.selection, 0.5))
+        );
+        assert_eq!(*image.get_pixel(2, 3), pixel(palette.dead));
+        assert_eq!(*image.get_pixel(0, 0), pixel(palette.dead));
+    }
+}
@@ -0,0 +1,27 @@
+// Rectangle of board cells between two corners, both included
+#[derive(Clone, Copy)]
+pub struct Selection {
+    // Cell where the selection started
+    pub anchor: (u32, u32),
+    // Cell at the opposite corner, following the mouse while selecting
+    pub corner: (u32, u32),
+}
+
+impl Selection {
+    pub fn new(cell: (u32, u32)) -> Self {
+        Selection {
+            anchor: cell,
+            corner: cell,
+        }
+    }
+
+    // Selected rectangle, as (min_x, min_y, max_x, max_y)
+    pub fn bounds(&self) -> (u32, u32, u32, u32) {
+        (
+            self.anchor.0.min(self.corner.0),
+            self.anchor.1.min(self.corner.1),
+            self.anchor.0.max(self.corner.0),
+            self.anchor.1.max(self.corner.1),
+        )
+    }
+}
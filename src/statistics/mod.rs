@@ -0,0 +1,266 @@
+// Time series of the board for experiments: a record per generation with the population, births,
+// deaths, bounding box, density and objects of each class, streamed as CSV or JSON Lines
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::objects::{Classifier, Object, CLASS_NAMES};
+use crate::GameOfLife;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum StatisticsFormat {
+    // A header line, then comma separated values, with empty bounds for an empty board
+    Csv,
+    // A JSON object per line, with null bounds for an empty board
+    JsonLines,
+}
+
+impl StatisticsFormat {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        match text {
+            "csv" => Ok(StatisticsFormat::Csv),
+            "jsonl" | "json" => Ok(StatisticsFormat::JsonLines),
+            _ => Err(format!(
+                "Unknown statistics format \"{}\", expected csv or jsonl",
+                text
+            )),
+        }
+    }
+
+    // Format of a file from its name, CSV unless it ends with .jsonl or .json
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension() {
+            Some(extension)
+                if extension.eq_ignore_ascii_case("jsonl")
+                    || extension.eq_ignore_ascii_case("json") =>
+            {
+                StatisticsFormat::JsonLines
+            }
+            _ => StatisticsFormat::Csv,
+        }
+    }
+}
+
+// Writes a record for each board it is given, to a file or to the standard output for "-".
+// Records are flushed as they are written, so the file can be followed while the program runs
+pub struct Statistics {
+    output: Box<dyn Write>,
+    format: StatisticsFormat,
+    // Board recorded last, for the births and deaths
+    previous: Option<GameOfLife<'static>>,
+    // Generation and edit count of the board recorded last
+    recorded: Option<(u64, u64)>,
+}
+
+impl Statistics {
+    pub fn create(path: &Path, format: StatisticsFormat) -> Result<Self, String> {
+        let output: Box<dyn Write> = if path == Path::new("-") {
+            Box::new(BufWriter::new(std::io::stdout()))
+        } else {
+            let file = File::create(path)
+                .map_err(|e| format!("Can't create {}: {}", path.display(), e))?;
+            Box::new(BufWriter::new(file))
+        };
+
+        let mut statistics = Statistics {
+            output,
+            format,
+            previous: None,
+            recorded: None,
+        };
+
+        if format == StatisticsFormat::Csv {
+            let mut header =
+                "generation,population,births,deaths,min_x,min_y,max_x,max_y,density".to_string();
+            for name in CLASS_NAMES.iter() {
+                header.push(',');
+                header.push_str(&column(name));
+            }
+            header.push('\n');
+
+            statistics.write(&header)?;
+        }
+
+        Ok(statistics)
+    }
+
+    // Writes the record of `board`, with the objects found on it, unless this board was just
+    // recorded. Births and deaths are counted since the previous record, so after an edit or an
+    // undo they are all the cells that changed
+    pub fn record(&mut self, board: &GameOfLife, objects: &[Object]) -> Result<(), String> {
+        if self.recorded == Some((board.generation, board.edits)) {
+            return Ok(());
+        }
+        self.recorded = Some((board.generation, board.edits));
+
+        let population = board.population();
+
+        let (births, deaths) = match &self.previous {
+            Some(previous) if (previous.width, previous.height) == (board.width, board.height) => {
+                board.changes_since(previous)
+            }
+            _ => (0, 0),
+        };
+        self.previous = Some(GameOfLife {
+            width: board.width,
+            height: board.height,
+            generation: board.generation,
+            edits: board.edits,
+            simulation: board
+                .simulation
+                .iter()
+                .map(|&&cell| if cell { &true } else { &false })
+                .collect(),
+        });
+
+        let bounds = board.population_bounds();
+        // Fraction of the board alive
+        let density = population as f64 / (board.width * board.height) as f64;
+
+        let mut counts = [0; CLASS_NAMES.len()];
+        for object in objects {
+            counts[object.class.index()] += 1;
+        }
+
+        let line = match self.format {
+            StatisticsFormat::Csv => {
+                let bounds = match bounds {
+                    Some((min_x, min_y, max_x, max_y)) => {
+                        format!("{},{},{},{}", min_x, min_y, max_x, max_y)
+                    }
+                    None => ",,,".to_string(),
+                };
+                let counts: Vec<String> = counts.iter().map(|count| count.to_string()).collect();
+
+                format!(
+                    "{},{},{},{},{},{},{}\n",
+                    board.generation,
+                    population,
+                    births,
+                    deaths,
+                    bounds,
+                    density,
+                    counts.join(",")
+                )
+            }
+            StatisticsFormat::JsonLines => {
+                let bounds = match bounds {
+                    Some((min_x, min_y, max_x, max_y)) => {
+                        format!("[{},{},{},{}]", min_x, min_y, max_x, max_y)
+                    }
+                    None => "null".to_string(),
+                };
+                let counts: Vec<String> = CLASS_NAMES
+                    .iter()
+                    .zip(&counts)
+                    .map(|(name, count)| format!("\"{}\":{}", column(name), count))
+                    .collect();
+
+                format!(
+                    "{{\"generation\":{},\"population\":{},\"births\":{},\"deaths\":{},\"bounds\":{},\"density\":{},\"objects\":{{{}}}}}\n",
+                    board.generation,
+                    population,
+                    births,
+                    deaths,
+                    bounds,
+                    density,
+                    counts.join(",")
+                )
+            }
+        };
+
+        self.write(&line)?;
+        self.output.flush().map_err(|e| e.to_string())
+    }
+
+    fn write(&mut self, text: &str) -> Result<(), String> {
+        self.output
+            .write_all(text.as_bytes())
+            .map_err(|e| e.to_string())
+    }
+}
+
+// Records the board in the statistics, if they are asked for
+pub fn record(
+    statistics: &mut Option<Statistics>,
+    classifier: &mut Classifier,
+    board: &GameOfLife,
+) -> Result<(), String> {
+    match statistics {
+        Some(statistics) => statistics.record(board, &classifier.find_objects(board)),
+        None => Ok(()),
+    }
+}
+
+// Name of the column of a class, "still_lifes" for "Still lifes"
+fn column(class_name: &str) -> String {
+    class_name.to_lowercase().replace(' ', "_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Records each board to a file of the format, and returns what was written
+    fn records(name: &str, format: StatisticsFormat, boards: &[&GameOfLife]) -> String {
+        let path = std::env::temp_dir().join(format!("{}-{}", std::process::id(), name));
+
+        let mut statistics = Statistics::create(&path, format).unwrap();
+        for board in boards {
+            let objects = Classifier::new().find_objects(board);
+            statistics.record(board, &objects).unwrap();
+        }
+        drop(statistics);
+
+        let written = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        written
+    }
+
+    #[test]
+    fn writes_csv() {
+        // A blinker, which turns from vertical to horizontal
+        let mut board = GameOfLife::new(8, 8);
+        for y in 1..=3 {
+            board.set(2, y, true);
+        }
+        let next = board.simulate();
+
+        assert_eq!(
+            records(
+                "statistics.csv",
+                StatisticsFormat::Csv,
+                &[&board, &next, &next]
+            ),
+            "generation,population,births,deaths,min_x,min_y,max_x,max_y,density,\
+             still_lifes,oscillators,spaceships,active\n\
+             0,3,0,0,2,1,2,3,0.046875,0,1,0,0\n\
+             1,3,2,2,1,2,3,2,0.046875,0,1,0,0\n"
+        );
+    }
+
+    #[test]
+    fn writes_json_lines() {
+        // An empty board, then a block drawn on it
+        let empty = GameOfLife::new(8, 8);
+        let mut block = GameOfLife::new(8, 8);
+        for &(x, y) in &[(1, 1), (2, 1), (1, 2), (2, 2)] {
+            block.set(x, y, true);
+        }
+
+        assert_eq!(
+            records(
+                "statistics.jsonl",
+                StatisticsFormat::JsonLines,
+                &[&empty, &block]
+            ),
+            "{\"generation\":0,\"population\":0,\"births\":0,\"deaths\":0,\"bounds\":null,\
+             \"density\":0,\"objects\":{\"still_lifes\":0,\"oscillators\":0,\"spaceships\":0,\
+             \"active\":0}}\n\
+             {\"generation\":0,\"population\":4,\"births\":4,\"deaths\":0,\"bounds\":[1,1,2,2],\
+             \"density\":0.0625,\"objects\":{\"still_lifes\":1,\"oscillators\":0,\"spaceships\":0,\
+             \"active\":0}}\n"
+        );
+    }
+}
@@ -0,0 +1,387 @@
+// Front-end drawing the board in a terminal with ANSI colours, for machines without a display,
+// over SSH for instance. The terminal is put in raw mode with stty, so it needs a Unix terminal
+
+use std::fmt::Write as _;
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::objects::Classifier;
+use crate::options::Options;
+use crate::render::{software, Activity, Style};
+use crate::statistics;
+use crate::GameOfLife;
+use nalgebra_glm as glm;
+
+// How long to wait for keys while paused, and how often the size of the terminal is checked, as
+// that runs stty
+const PAUSED_REFRESH: Duration = Duration::from_millis(500);
+
+const MIN_DELAY: u32 = 10;
+const MAX_DELAY: u32 = 5000;
+
+// Characters drawing the cells
+#[derive(Clone, Copy, PartialEq)]
+pub enum Glyphs {
+    // Upper half blocks coloured on both halves, 1x2 cells per character
+    HalfBlocks,
+    // Braille dots of a single colour, 2x4 cells per character
+    Braille,
+}
+
+impl Glyphs {
+    // Cells drawn by a character
+    fn cells(self) -> (u32, u32) {
+        match self {
+            Glyphs::HalfBlocks => (1, 2),
+            Glyphs::Braille => (2, 4),
+        }
+    }
+}
+
+// Raw mode and alternate screen, restored when dropped
+struct RawTerminal {
+    // Settings from stty -g
+    saved: String,
+}
+
+impl RawTerminal {
+    fn enter() -> Result<Self, String> {
+        let saved = stty(&["-g"])?;
+        stty(&["raw", "-echo"])?;
+
+        // Alternate screen, cursor hidden
+        print!("\x1b[?1049h\x1b[?25l");
+        let _ = std::io::stdout().flush();
+
+        Ok(RawTerminal {
+            saved: saved.trim().to_string(),
+        })
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        print!("\x1b[0m\x1b[?25h\x1b[?1049l");
+        let _ = std::io::stdout().flush();
+        let _ = stty(&[&self.saved]);
+    }
+}
+
+// Runs stty on the terminal of the standard input, returning what it printed
+fn stty(args: &[&str]) -> Result<String, String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .output()
+        .map_err(|e| format!("Can't run stty: {}", e))?;
+
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    } else {
+        Err(format!(
+            "stty failed, the standard input must be a terminal: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+// Columns and rows of the terminal
+fn size() -> Result<(u32, u32), String> {
+    let text = stty(&["size"])?;
+    let mut numbers = text.split_whitespace().map(str::parse::<u32>);
+
+    match (numbers.next(), numbers.next()) {
+        (Some(Ok(rows)), Some(Ok(columns))) => Ok((columns, rows)),
+        _ => Err(format!("Unexpected terminal size \"{}\"", text.trim())),
+    }
+}
+
+// Runs the simulation in the terminal until Q is pressed. Space pauses, N steps a generation,
+// + / - change the speed and the arrows pan
+pub fn run(options: &Options, board: GameOfLife) -> Result<(), String> {
+    let mut board = board;
+    let mut activity = Activity::new(board.width, board.height);
+    activity.observe(&board);
+
+    let mut classifier = Classifier::new();
+    let mut statistics = options.statistics()?;
+    statistics::record(&mut statistics, &mut classifier, &board)?;
+
+    for _ in 0..options.generations {
+        board = board.simulate();
+        activity.observe(&board);
+        statistics::record(&mut statistics, &mut classifier, &board)?;
+    }
+
+    let style = options.style();
+    let glyphs = if options.braille {
+        Glyphs::Braille
+    } else {
+        Glyphs::HalfBlocks
+    };
+
+    let _terminal = RawTerminal::enter()?;
+
+    // Keys are read on their own thread, as reading the standard input blocks
+    let (sender, keys) = mpsc::channel();
+    thread::spawn(move || {
+        let mut buffer = [0u8; 16];
+        let mut stdin = std::io::stdin();
+
+        while let Ok(count) = stdin.read(&mut buffer) {
+            if count == 0 || sender.send(buffer[..count].to_vec()).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut delay = options.delay.clamp(MIN_DELAY, MAX_DELAY);
+    let mut paused = false;
+    let mut next_step = Instant::now() + Duration::from_millis(delay as u64);
+
+    let (mut columns, mut rows) = size()?;
+    let mut size_checked = Instant::now();
+    // Cells of the region left of and above the terminal
+    let mut pan = (0, 0);
+
+    loop {
+        // Frames step at most a generation, so every generation is recorded
+        let objects = classifier.find_objects(&board);
+        if let Some(statistics) = &mut statistics {
+            statistics.record(&board, &objects)?;
+        }
+
+        if size_checked.elapsed() >= PAUSED_REFRESH {
+            let (new_columns, new_rows) = size()?;
+            columns = new_columns;
+            rows = new_rows;
+            size_checked = Instant::now();
+        }
+
+        // Cells the terminal shows, above the status row
+        let (cell_width, cell_height) = glyphs.cells();
+        let visible = (columns * cell_width, rows.saturating_sub(1) * cell_height);
+
+        // Panning stops with the last cells of the region on the terminal
+        let (min_x, min_y, max_x, max_y) = options.crop.region(&board, 0);
+        pan.0 = u32::min(pan.0, (max_x - min_x + 1).saturating_sub(visible.0));
+        pan.1 = u32::min(pan.1, (max_y - min_y + 1).saturating_sub(visible.1));
+
+        let mut frame = draw(
+            &style,
+            &board,
+            &activity,
+            &objects,
+            (min_x + pan.0, min_y + pan.1, max_x, max_y),
+            glyphs,
+            (columns, rows),
+        );
+
+        // Status in reverse video on the last row
+        let status: String = status(&board, paused, delay)
+            .chars()
+            .take(columns as usize)
+            .collect();
+        frame.push_str(&format!("\x1b[{};1H\x1b[7m{}\x1b[0m", rows.max(1), status));
+
+        let mut stdout = std::io::stdout();
+        stdout
+            .write_all(frame.as_bytes())
+            .and_then(|_| stdout.flush())
+            .map_err(|e| e.to_string())?;
+
+        let timeout = if paused {
+            PAUSED_REFRESH
+        } else {
+            next_step.saturating_duration_since(Instant::now())
+        };
+
+        let mut step = false;
+
+        match keys.recv_timeout(timeout) {
+            Ok(bytes) => {
+                // A lone escape is the Escape key, longer sequences are other keys
+                if bytes == [0x1b] {
+                    return Ok(());
+                }
+
+                // A quarter of the terminal, at least a character
+                let pan_step = (
+                    (visible.0 / 4).max(cell_width),
+                    (visible.1 / 4).max(cell_height),
+                );
+
+                let mut rest = &bytes[..];
+                while let Some((&byte, after)) = rest.split_first() {
+                    rest = after;
+
+                    match byte {
+                        // The arrows are Escape [ or Escape O, then A to D
+                        0x1b => {
+                            if let [b'[' | b'O', arrow @ b'A'..=b'D', after @ ..] = rest {
+                                match arrow {
+                                    b'A' => pan.1 = pan.1.saturating_sub(pan_step.1),
+                                    b'B' => pan.1 += pan_step.1,
+                                    b'C' => pan.0 += pan_step.0,
+                                    _ => pan.0 = pan.0.saturating_sub(pan_step.0),
+                                }
+                                rest = after;
+                            }
+                        }
+                        // Ctrl + C doesn't interrupt in raw mode
+                        b'q' | b'Q' | 0x03 => return Ok(()),
+                        b' ' => {
+                            paused = !paused;
+                            next_step = Instant::now() + Duration::from_millis(delay as u64);
+                        }
+                        b'n' | b'N' => {
+                            paused = true;
+                            step = true;
+                        }
+                        b'+' | b'=' => delay = (delay / 2).max(MIN_DELAY),
+                        b'-' => delay = (delay * 2).min(MAX_DELAY),
+                        _ => {}
+                    }
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                if !paused {
+                    step = true;
+                    next_step = Instant::now() + Duration::from_millis(delay as u64);
+                }
+            }
+            Err(RecvTimeoutError::Disconnected) => return Ok(()),
+        }
+
+        if step {
+            board = board.simulate();
+            activity.observe(&board);
+        }
+    }
+}
+
+fn status(board: &GameOfLife, paused: bool, delay: u32) -> String {
+    format!(
+        "Generation {}  Population {}  {}  Space pause  N step  +/- speed  Arrows pan  Q quit",
+        board.generation,
+        board.population(),
+        if paused {
+            "Paused".to_string()
+        } else {
+            format!("Every {} ms", delay)
+        }
+    )
+}
+
+// The escape sequences drawing the cells of `region` from its top left corner, as many as fit in
+// the columns and rows of the terminal, keeping the last row for the status
+fn draw(
+    style: &Style,
+    board: &GameOfLife,
+    activity: &Activity,
+    objects: &[crate::objects::Object],
+    (min_x, min_y, max_x, max_y): (u32, u32, u32, u32),
+    glyphs: Glyphs,
+    (columns, rows): (u32, u32),
+) -> String {
+    let values = style.cell_values(board, activity);
+    let highlights = style.object_highlights(board.width, board.height, objects);
+
+    // Colour of a cell of the region and whether it is alive, cells past the region being dead
+    let cell = |x: u32, y: u32| {
+        if min_x + x > max_x || min_y + y > max_y {
+            return (style.palette.dead, false);
+        }
+
+        let index = crate::index(min_x + x, min_y + y, board.width) as usize;
+        (
+            software::cell_color(style, values[index], highlights[index]),
+            *board.simulation[index],
+        )
+    };
+
+    // Characters covering the region, the last ones partly
+    let (cell_width, cell_height) = glyphs.cells();
+    let width = ((max_x - min_x + cell_width) / cell_width).min(columns);
+    let height = ((max_y - min_y + cell_height) / cell_height).min(rows.saturating_sub(1));
+
+    let mut frame = String::new();
+    let dead = software::to_rgb(style.palette.dead);
+
+    for row in 0..height {
+        // Writing to a String can't fail
+        let _ = write!(frame, "\x1b[{};1H", row + 1);
+
+        // Only the colours that change are written
+        let mut colors = None;
+
+        for column in 0..width {
+            let (x, y) = (column * cell_width, row * cell_height);
+
+            let (character, foreground, background) = match glyphs {
+                Glyphs::HalfBlocks => {
+                    let (top, _) = cell(x, y);
+                    let (bottom, _) = cell(x, y + 1);
+                    ('▀', software::to_rgb(top), software::to_rgb(bottom))
+                }
+                Glyphs::Braille => {
+                    // Bits of the dots, column by column
+                    const DOTS: [[u32; 4]; 2] =
+                        [[0x01, 0x02, 0x04, 0x40], [0x08, 0x10, 0x20, 0x80]];
+
+                    let mut dots = 0;
+                    let mut sum = glm::vec3(0.0, 0.0, 0.0);
+                    let mut count = 0.0;
+
+                    for (dx, column_dots) in DOTS.iter().enumerate() {
+                        for (dy, dot) in column_dots.iter().enumerate() {
+                            let (color, alive) = cell(x + dx as u32, y + dy as u32);
+                            if alive {
+                                dots |= dot;
+                                sum += color;
+                                count += 1.0;
+                            }
+                        }
+                    }
+
+                    let foreground = if count > 0.0 {
+                        software::to_rgb(sum / count)
+                    } else {
+                        software::to_rgb(style.palette.alive)
+                    };
+                    (
+                        std::char::from_u32(0x2800 + dots).unwrap_or(' '),
+                        foreground,
+                        dead,
+                    )
+                }
+            };
+
+            if colors != Some((foreground, background)) {
+                let _ = write!(
+                    frame,
+                    "\x1b[38;2;{};{};{}m\x1b[48;2;{};{};{}m",
+                    foreground[0],
+                    foreground[1],
+                    foreground[2],
+                    background[0],
+                    background[1],
+                    background[2]
+                );
+                colors = Some((foreground, background));
+            }
+            frame.push(character);
+        }
+
+        // Clears the rest of the row
+        frame.push_str("\x1b[0m\x1b[K");
+    }
+
+    // Clears the rows under the board
+    let _ = write!(frame, "\x1b[{};1H\x1b[J", height + 1);
+
+    frame
+}
@@ -0,0 +1,135 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::GameOfLife;
+
+// Bounds on the memory used by the history: the oldest edits are forgotten first
+const MAX_EDITS: usize = 1000;
+const MAX_CHANGES: usize = 4_000_000;
+
+// A cell whose value was changed by an edit
+#[derive(Clone, Copy)]
+pub struct CellChange {
+    pub x: u32,
+    pub y: u32,
+    pub before: bool,
+    pub after: bool,
+}
+
+// One undoable operation on the board, such as a draw stroke or a paste
+pub struct Edit {
+    pub name: &'static str,
+    changes: Vec<CellChange>,
+    // Position in `changes` of each changed cell, so changing a cell twice keeps one change
+    positions: HashMap<(u32, u32), usize>,
+}
+
+impl Edit {
+    pub fn new(name: &'static str) -> Self {
+        Edit {
+            name,
+            changes: Vec::new(),
+            positions: HashMap::new(),
+        }
+    }
+
+    pub fn push(&mut self, change: CellChange) {
+        match self.positions.get(&(change.x, change.y)) {
+            Some(&position) => self.changes[position].after = change.after,
+            None => {
+                self.positions
+                    .insert((change.x, change.y), self.changes.len());
+                self.changes.push(change);
+            }
+        }
+    }
+}
+
+impl Extend<CellChange> for Edit {
+    fn extend<I: IntoIterator<Item = CellChange>>(&mut self, changes: I) {
+        for change in changes {
+            self.push(change);
+        }
+    }
+}
+
+// Undo and redo stacks of the edits made to the board. They only hold the cells each edit
+// changed, and don't rewind the simulation: undoing sets those cells back on the current board
+pub struct UndoHistory {
+    done: VecDeque<Edit>,
+    undone: Vec<Edit>,
+    // Cell changes held by both stacks
+    stored_changes: usize,
+}
+
+impl UndoHistory {
+    pub fn new() -> Self {
+        UndoHistory {
+            done: VecDeque::new(),
+            undone: Vec::new(),
+            stored_changes: 0,
+        }
+    }
+
+    pub fn record(&mut self, mut edit: Edit) {
+        edit.changes.retain(|change| change.before != change.after);
+        edit.positions = HashMap::new();
+
+        if edit.changes.is_empty() {
+            return;
+        }
+
+        // A new edit starts a new branch of history
+        for undone in self.undone.drain(..) {
+            self.stored_changes -= undone.changes.len();
+        }
+
+        self.stored_changes += edit.changes.len();
+        self.done.push_back(edit);
+
+        while self.done.len() > 1
+            && (self.done.len() > MAX_EDITS || self.stored_changes > MAX_CHANGES)
+        {
+            if let Some(oldest) = self.done.pop_front() {
+                self.stored_changes -= oldest.changes.len();
+            }
+        }
+    }
+
+    // Reverts the last edit
+    pub fn undo(&mut self, board: &mut GameOfLife) {
+        let edit = match self.done.pop_back() {
+            Some(edit) => edit,
+            None => return,
+        };
+
+        for change in edit.changes.iter().rev() {
+            board.set(change.x, change.y, change.before);
+        }
+
+        self.undone.push(edit);
+    }
+
+    // Applies the last undone edit again
+    pub fn redo(&mut self, board: &mut GameOfLife) {
+        let edit = match self.undone.pop() {
+            Some(edit) => edit,
+            None => return,
+        };
+
+        for change in &edit.changes {
+            board.set(change.x, change.y, change.after);
+        }
+
+        self.done.push_back(edit);
+    }
+
+    // Name of the edit the next undo reverts
+    pub fn next_undo(&self) -> Option<&'static str> {
+        self.done.back().map(|edit| edit.name)
+    }
+
+    // Name of the edit the next redo applies
+    pub fn next_redo(&self) -> Option<&'static str> {
+        self.undone.last().map(|edit| edit.name)
+    }
+}